@@ -0,0 +1,69 @@
+//! How long it takes to encode square boards of increasing size, with one colour per row.
+//!
+//! The clauses are loaded into a backend which discards them, so only generating them is timed, not solving or writing them out.
+//! Run with `cargo bench --bench encoding`.
+
+use std::num::NonZero;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use permanganate::backend::Backend;
+use permanganate::builder::SquareBoardBuilder;
+use permanganate::shape::SquareStep;
+use permanganate::{Board, Builder, Location, SolveOptions, SolverFailure};
+use varisat::Lit;
+
+/// An `n` by `n` board with termini at either end of every row, one colour per row.
+fn board(n: usize) -> Board<SquareStep> {
+    let mut builder = SquareBoardBuilder::with_dims((NonZero::new(n).unwrap(), NonZero::new(n).unwrap()));
+    for row in 0..n {
+        // more colours than the alphabet has letters; the encoding does not care how they display
+        let display = char::from_u32(0x100 + row as u32).unwrap();
+        builder.add_termini(display, (Location(0, row), Location(n - 1, row)));
+    }
+
+    builder.build().unwrap()
+}
+
+/// A backend which counts the clauses it is given, then gives up rather than solve them.
+#[derive(Default)]
+struct Discard {
+    clauses: usize,
+}
+
+impl Backend for Discard {
+    fn add_clause(&mut self, clause: &[Lit]) {
+        criterion::black_box(clause);
+        self.clauses += 1;
+    }
+
+    fn assume(&mut self, _assumptions: &[Lit]) {}
+
+    fn solve(&mut self) -> Result<bool, SolverFailure> {
+        Err(SolverFailure::Backend(format!("discarded {} clauses", self.clauses)))
+    }
+
+    fn model(&self) -> Option<Vec<Lit>> {
+        None
+    }
+
+    fn failed_core(&self) -> Option<Vec<Lit>> {
+        None
+    }
+}
+
+fn encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encoding");
+    group.sample_size(10);
+
+    // propagation is timed separately from encoding, if at all
+    let options = SolveOptions { propagate: false, ..Default::default() };
+    for n in (5..=30).step_by(5) {
+        let board = board(n);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &board, |b, board| b.iter(|| board.clone().solve_with_backend(options.clone(), Discard::default)));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, encoding);
+criterion_main!(benches);
@@ -1 +1,8 @@
 pub(crate) type AffiliationID = usize;
+
+/// The affiliation of the termini which display as `display`, given the display of every affiliation from 1 onwards, in order.
+///
+/// Should more than one pair of termini display alike, the latest are the ones a player would see.
+pub(crate) fn affiliation_of(displays: &[char], display: char) -> Option<AffiliationID> {
+    displays.iter().rposition(|other| *other == display).map(|index| index + 1)
+}
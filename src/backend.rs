@@ -0,0 +1,494 @@
+//! SAT solvers which can stand behind a [`GraphSolver`](crate::solver::GraphSolver).
+//!
+//! Clauses are generated once, in terms of [`varisat`]'s [`Lit`]s, and handed to a [`Backend`] to be solved.
+//! [`varisat::Solver`] is the default backend; [`DimacsProcess`] runs an external solver binary, and, with the `cdcl` feature, [`Cdcl`] is a small solver written for this crate.
+//! [`Counted`] wraps any of them to count its conflicts.
+
+use std::cell::RefCell;
+use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use varisat::checker::{CheckedProofStep, CheckerData, ProofProcessor};
+use varisat::{CnfFormula, ExtendFormula, Lit, Var};
+
+#[cfg(feature = "cdcl")]
+pub use cdcl::Cdcl;
+
+use crate::options::{CancelToken, ProgressCallback, SolveOptions, SolveProgress, PROGRESS_INTERVAL};
+use crate::graph::SolverFailure;
+
+#[cfg(feature = "cdcl")]
+mod cdcl;
+
+/// An incremental SAT solver.
+///
+/// Clauses accumulate across calls to [`solve`](Self::solve), while assumptions hold until they are replaced.
+pub trait Backend {
+    /// Add a clause, which must hold in every model from now on.
+    fn add_clause(&mut self, clause: &[Lit]);
+    /// Add every clause of `formula`.
+    fn add_formula(&mut self, formula: &CnfFormula) {
+        formula.iter().for_each(|clause| self.add_clause(clause));
+    }
+    /// Replace the current assumptions, which must hold in models found by later calls to [`solve`](Self::solve).
+    fn assume(&mut self, assumptions: &[Lit]);
+    /// Search for a model, returning whether one exists.
+    fn solve(&mut self) -> Result<bool, SolverFailure>;
+    /// The model found by the last call to [`solve`](Self::solve), if it succeeded.
+    ///
+    /// Every variable up to the highest one used is present, in order, so the literal for a variable is at the index of that variable.
+    fn model(&self) -> Option<Vec<Lit>>;
+    /// Some subset of the assumptions which cannot all hold, if the last call to [`solve`](Self::solve) failed.
+    ///
+    /// This need not be minimal; in particular, a backend without better information may return every assumption.
+    fn failed_core(&self) -> Option<Vec<Lit>>;
+    /// Counts of the work done by every call to [`solve`](Self::solve) so far, if this backend keeps them.
+    ///
+    /// The default is [`None`]; of the backends in this crate, only `Cdcl` from the `cdcl` feature keeps counts, since [`varisat`] does not expose its own.
+    /// [`Counted`] counts the conflicts of any backend which reports them through [`conflict`], [`varisat`] included.
+    fn statistics(&self) -> Option<Statistics> {
+        None
+    }
+    /// Prepare to call [`conflict`] at every conflict while solving, so that the limits in [`SolveOptions`] can stop a call to [`solve`](Self::solve) partway.
+    ///
+    /// This is called before any clause is added, and only if some limit is set.
+    /// The default does nothing, which suits a backend that always calls [`conflict`], or one that cannot; the limits are still checked between calls to [`solve`](Self::solve).
+    ///
+    /// A backend stopped partway by a limit, with [`SolverFailure::Timeout`] or [`SolverFailure::Cancelled`], need not be usable again; [`varisat::Solver`] is not.
+    /// Callers must start again from a fresh backend rather than solve with it again.
+    fn interruptible(&mut self) {}
+}
+
+thread_local! {
+    /// The limits of the solve in progress on this thread, if it has any.
+    static LIMITS: RefCell<Option<Arc<Limits>>> = const { RefCell::new(None) };
+}
+
+/// The limits set in [`SolveOptions`], and how far a solve has got towards them, shared by every thread working on it.
+pub(crate) struct Limits {
+    deadline: Option<Instant>,
+    conflict_budget: Option<u64>,
+    cancel: Option<CancelToken>,
+    progress: Option<ProgressCallback>,
+    // only taken if needed, since `Instant` is unavailable on some targets
+    start: Option<Instant>,
+    conflicts: AtomicU64,
+    // the limits of the solve this one is part of, which hold as well
+    parent: Option<Arc<Limits>>,
+}
+
+impl Limits {
+    /// Whether any limit is exceeded, and if so, how to fail.
+    fn check(&self) -> Result<(), SolverFailure> {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            return Err(SolverFailure::Cancelled);
+        }
+        if self.conflict_budget.is_some_and(|budget| self.conflicts.load(Ordering::Relaxed) > budget) || self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(SolverFailure::Timeout);
+        }
+
+        self.parent.as_deref().map_or(Ok(()), Limits::check)
+    }
+
+    /// Limits which stop at `cancel`, if given, as well as at `parent`, and count their own conflicts apart from it.
+    fn within(parent: Option<Arc<Limits>>, cancel: Option<CancelToken>) -> Arc<Self> {
+        Arc::new(Self {
+            deadline: None,
+            conflict_budget: None,
+            cancel,
+            progress: None,
+            start: None,
+            conflicts: AtomicU64::new(0),
+            parent,
+        })
+    }
+
+    /// Count a conflict against these limits and those of every parent, reporting progress as due, and fail if any is exceeded.
+    fn conflict(&self) -> Result<(), SolverFailure> {
+        let conflicts = self.conflicts.fetch_add(1, Ordering::Relaxed) + 1;
+        if let (Some(ProgressCallback(callback)), 0) = (&self.progress, conflicts % PROGRESS_INTERVAL) {
+            callback(SolveProgress { conflicts, elapsed: self.start.unwrap().elapsed() });
+        }
+
+        if let Some(parent) = &self.parent {
+            parent.conflict()?;
+        }
+        self.check()
+    }
+}
+
+/// Keeps the limits of a solve in force on this thread until dropped; see [`watch`].
+pub(crate) struct Watch {
+    // the limits to put back, if these replaced any
+    restore: Option<Option<Arc<Limits>>>,
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        if let Some(previous) = self.restore.take() {
+            LIMITS.set(previous);
+        }
+    }
+}
+
+/// Put the limits in `options` in force on this thread until the returned guard is dropped.
+///
+/// Only the outermost solve sets limits, so a solve made up of several others is limited as a whole.
+pub(crate) fn watch(options: &SolveOptions) -> Watch {
+    LIMITS.with_borrow_mut(|limits| {
+        if limits.is_some() || !options.limited() {
+            return Watch { restore: None };
+        }
+
+        *limits = Some(Arc::new(Limits {
+            deadline: options.deadline,
+            conflict_budget: options.conflict_budget,
+            cancel: options.cancel.clone(),
+            progress: options.progress.clone(),
+            start: (options.deadline.is_some() || options.progress.is_some()).then(Instant::now),
+            conflicts: AtomicU64::new(0),
+            parent: None,
+        }));
+        Watch { restore: Some(None) }
+    })
+}
+
+/// The limits in force on this thread, to be shared with others working on the same solve by [`adopt`].
+pub(crate) fn watched() -> Option<Arc<Limits>> {
+    LIMITS.with_borrow(Clone::clone)
+}
+
+/// Put `limits`, from [`watched`] on another thread, in force on this one until the returned guard is dropped.
+pub(crate) fn adopt(limits: Option<Arc<Limits>>) -> Watch {
+    Watch { restore: Some(LIMITS.replace(limits)) }
+}
+
+/// Put limits in force on this thread which stop at `cancel`, as well as at `parent`, from [`watched`] on another thread, until the returned guard is dropped.
+///
+/// Conflicts counted here also count towards `parent`, which reports the progress of them all.
+#[cfg(feature = "parallel")]
+pub(crate) fn adopt_within(parent: Option<Arc<Limits>>, cancel: &CancelToken) -> Watch {
+    adopt(Some(Limits::within(parent, Some(cancel.clone()))))
+}
+
+/// Fail if any limit of the solve in progress on this thread is exceeded.
+pub(crate) fn check_limits() -> Result<(), SolverFailure> {
+    LIMITS.with_borrow(|limits| limits.as_deref().map_or(Ok(()), Limits::check))
+}
+
+/// Count a conflict against the limits of the solve in progress on this thread, if it has any, and fail if any is exceeded.
+///
+/// A [`Backend`] should call this at every conflict, stopping its call to [`solve`](Backend::solve) with the error, if any; see [`Backend::interruptible`].
+/// Every [`PROGRESS_INTERVAL`] conflicts, this also reports to [`SolveOptions::progress`].
+pub fn conflict() -> Result<(), SolverFailure> {
+    watched().map_or(Ok(()), |limits| limits.conflict())
+}
+
+/// Passes every conflict of a [`varisat::Solver`] on to [`conflict`]; checking its proof is the only way to see into one while it runs.
+struct Interrupter;
+
+impl ProofProcessor for Interrupter {
+    fn process_step(&mut self, step: &CheckedProofStep, _: CheckerData) -> Result<(), anyhow::Error> {
+        // each conflict teaches the solver a clause, which is redundant
+        if let CheckedProofStep::AtClause { redundant: true, .. } = step {
+            conflict().map_err(|_| anyhow::anyhow!("interrupted"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A backend which counts the conflicts `B` reports through [`conflict`] as it solves, and gives them as its [`statistics`](Backend::statistics) if `B` keeps none of its own.
+///
+/// `B` is made [`interruptible`](Backend::interruptible) so that it reports every conflict, which for [`varisat::Solver`] means checking its proof, slowing it by around half again.
+/// A backend which cannot report its conflicts, such as [`DimacsProcess`], is counted as having none.
+/// See [`Rating::sat`](crate::rating::Rating::sat).
+pub struct Counted<B: Backend> {
+    inner: B,
+    conflicts: u64,
+}
+
+impl<B: Backend> Counted<B> {
+    /// Count the conflicts of `inner`, which should not yet have any clauses.
+    pub fn new(mut inner: B) -> Self {
+        inner.interruptible();
+        Self { inner, conflicts: 0 }
+    }
+}
+
+impl<B: Backend> Backend for Counted<B> {
+    fn add_clause(&mut self, clause: &[Lit]) {
+        self.inner.add_clause(clause);
+    }
+
+    fn add_formula(&mut self, formula: &CnfFormula) {
+        self.inner.add_formula(formula);
+    }
+
+    fn assume(&mut self, assumptions: &[Lit]) {
+        self.inner.assume(assumptions);
+    }
+
+    fn solve(&mut self) -> Result<bool, SolverFailure> {
+        // count conflicts apart from those of the rest of the solve, which still limits this one
+        let limits = Limits::within(watched(), None);
+        let result = {
+            let _limits = adopt(Some(limits.clone()));
+            self.inner.solve()
+        };
+        self.conflicts += limits.conflicts.load(Ordering::Relaxed);
+
+        result
+    }
+
+    fn model(&self) -> Option<Vec<Lit>> {
+        self.inner.model()
+    }
+
+    fn failed_core(&self) -> Option<Vec<Lit>> {
+        self.inner.failed_core()
+    }
+
+    fn statistics(&self) -> Option<Statistics> {
+        self.inner.statistics().or(Some(Statistics { conflicts: self.conflicts, decisions: None }))
+    }
+
+    fn interruptible(&mut self) {
+        // the inner backend already is, and being made so twice would count every conflict twice over
+    }
+}
+
+/// Counts of the work a [`Backend`] did while solving.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Statistics {
+    /// Assignments found to break some clause, each of which the solver backed out of.
+    pub conflicts: u64,
+    /// Literals the solver chose to assign, including assumptions, if the solver says; [`varisat`] does not.
+    pub decisions: Option<u64>,
+}
+
+impl Backend for varisat::Solver<'_> {
+    fn add_clause(&mut self, clause: &[Lit]) {
+        ExtendFormula::add_clause(self, clause);
+    }
+
+    fn add_formula(&mut self, formula: &CnfFormula) {
+        varisat::Solver::add_formula(self, formula);
+    }
+
+    fn assume(&mut self, assumptions: &[Lit]) {
+        varisat::Solver::assume(self, assumptions);
+    }
+
+    fn solve(&mut self) -> Result<bool, SolverFailure> {
+        // once interrupted, the solver cannot be used again, but the limits say why
+        varisat::Solver::solve(self).map_err(|err| check_limits().err().unwrap_or(SolverFailure::Backend(err.to_string())))
+    }
+
+    fn model(&self) -> Option<Vec<Lit>> {
+        varisat::Solver::model(self)
+    }
+
+    fn failed_core(&self) -> Option<Vec<Lit>> {
+        varisat::Solver::failed_core(self).map(|core| core.to_vec())
+    }
+
+    /// Check the solver's proof as it runs, which slows it by around half again.
+    ///
+    /// Once the solver is stopped partway, it cannot be used again, and a later call to [`solve`](Backend::solve) panics.
+    fn interruptible(&mut self) {
+        // the solver borrows its processors for as long as it lives, so one must be leaked, but an `Interrupter` has no size, so leaking one allocates nothing
+        self.add_proof_processor(Box::leak(Box::new(Interrupter)));
+    }
+}
+
+/// A backend which runs an external solver binary such as `kissat` or `cadical` for every call to [`solve`](Backend::solve).
+///
+/// The binary is given the problem in DIMACS CNF format on standard input, with the assumptions as unit clauses.
+/// It must report its answer on standard output in the format of the SAT competitions, i.e. an `s SATISFIABLE` or `s UNSATISFIABLE` line and, for the former, `v` lines listing the model.
+/// Solvers which only write their model to a file, like `minisat`, need a wrapper script.
+///
+/// Since the binary knows nothing of the assumptions, the [`failed_core`](Backend::failed_core) is always every assumption.
+#[derive(Clone, Debug)]
+pub struct DimacsProcess {
+    program: OsString,
+    args: Vec<OsString>,
+    clauses: Vec<Vec<Lit>>,
+    assumptions: Vec<Lit>,
+    num_vars: usize,
+    model: Option<Vec<Lit>>,
+    failed: bool,
+}
+
+impl DimacsProcess {
+    /// Create a backend which runs `program`, which is looked up as by [`Command::new`].
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Self {
+            program: program.as_ref().to_owned(),
+            args: Vec::new(),
+            clauses: Vec::new(),
+            assumptions: Vec::new(),
+            num_vars: 0,
+            model: None,
+            failed: false,
+        }
+    }
+
+    /// Pass `arg` to the binary, after any arguments already added.
+    pub fn arg(&mut self, arg: impl AsRef<OsStr>) -> &mut Self {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    #[inline]
+    fn note_vars(&mut self, lits: &[Lit]) {
+        self.num_vars = lits.iter().map(|lit| lit.index() + 1).fold(self.num_vars, usize::max);
+    }
+
+    /// Write the clauses and assumptions in DIMACS CNF format.
+    fn write_problem(&self, out: impl Write) -> std::io::Result<()> {
+        let mut out = BufWriter::new(out);
+        writeln!(out, "p cnf {} {}", self.num_vars, self.clauses.len() + self.assumptions.len())?;
+        for clause in &self.clauses {
+            for lit in clause {
+                write!(out, "{} ", lit.to_dimacs())?;
+            }
+            writeln!(out, "0")?;
+        }
+        for lit in &self.assumptions {
+            writeln!(out, "{} 0", lit.to_dimacs())?;
+        }
+        out.flush()
+    }
+}
+
+impl Backend for DimacsProcess {
+    fn add_clause(&mut self, clause: &[Lit]) {
+        self.note_vars(clause);
+        self.clauses.push(clause.to_vec());
+    }
+
+    fn assume(&mut self, assumptions: &[Lit]) {
+        self.note_vars(assumptions);
+        self.assumptions = assumptions.to_vec();
+    }
+
+    fn solve(&mut self) -> Result<bool, SolverFailure> {
+        self.model = None;
+        self.failed = false;
+
+        let failure = |reason: String| SolverFailure::Backend(format!("{}: {}", self.program.to_string_lossy(), reason));
+
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| failure(err.to_string()))?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // feed the problem from another thread so neither pipe fills up while the other is waiting
+        let (written, answer) = thread::scope(|scope| {
+            let writer = scope.spawn(|| self.write_problem(stdin));
+
+            let answer = read_model(BufReader::new(stdout), self.num_vars);
+            if answer.is_err() {
+                // the binary may still be running and not reading, which would leave the writer stuck
+                let _ = child.kill();
+            }
+
+            (writer.join().unwrap(), answer)
+        });
+
+        // reap the binary on every path, so that it does not linger as a zombie
+        let waited = child.wait();
+        let answer = answer.map_err(failure)?;
+        waited.map_err(|err| failure(err.to_string()))?;
+        // the binary may stop reading once it knows the answer, so a broken pipe does not matter if an answer came back
+        let model = match answer {
+            Some(model) => model,
+            None => return Err(failure(match written {
+                Err(err) => err.to_string(),
+                Ok(()) => "no answer".to_string(),
+            })),
+        };
+
+        self.failed = model.is_none();
+        self.model = model;
+        Ok(self.model.is_some())
+    }
+
+    fn model(&self) -> Option<Vec<Lit>> {
+        self.model.clone()
+    }
+
+    fn failed_core(&self) -> Option<Vec<Lit>> {
+        self.failed.then(|| self.assumptions.clone())
+    }
+}
+
+/// Read the answer of a SAT solver from `input`, returning [`None`] if there is no answer, `Some(None)` if the problem is unsatisfiable, or the model otherwise.
+///
+/// Both the format of the SAT competitions (`s` and `v` lines) and the bare list of literals written by solvers such as `minisat` are understood.
+/// The model contains every variable below `num_vars` and any others mentioned; those not mentioned are false.
+pub(crate) fn read_model(input: impl BufRead, num_vars: usize) -> Result<Option<Option<Vec<Lit>>>, String> {
+    let mut satisfiable = None;
+    let mut model = vec![None; num_vars];
+
+    for line in input.lines() {
+        let line = line.map_err(|err| err.to_string())?;
+        let mut words = line.split_whitespace().peekable();
+        match words.peek().copied() {
+            None | Some("c") => continue,
+            Some("s") => {
+                words.next();
+                satisfiable = match words.next() {
+                    Some("SATISFIABLE") => Some(true),
+                    Some("UNSATISFIABLE") => Some(false),
+                    other => return Err(format!("unexpected answer {:?}", other.unwrap_or_default())),
+                };
+                continue;
+            }
+            Some("SAT") => {
+                satisfiable = Some(true);
+                continue;
+            }
+            Some("UNSAT") => {
+                satisfiable = Some(false);
+                continue;
+            }
+            Some("v") => {
+                words.next();
+            }
+            _ => {}
+        }
+
+        for word in words {
+            let number = word.parse::<isize>().map_err(|_| format!("bad literal {:?}", word))?;
+            if number == 0 {
+                continue;
+            }
+
+            let lit = Lit::from_dimacs(number);
+            if model.len() <= lit.index() {
+                model.resize(lit.index() + 1, None);
+            }
+            model[lit.index()] = Some(lit);
+            // a bare list of literals is a model in itself
+            satisfiable.get_or_insert(true);
+        }
+    }
+
+    Ok(satisfiable.map(|satisfiable| satisfiable.then(|| model.into_iter()
+        .enumerate()
+        .map(|(index, lit)| lit.unwrap_or(Var::from_index(index).negative()))
+        .collect())))
+}
@@ -0,0 +1,442 @@
+//! A compact conflict-driven clause learning solver.
+
+use varisat::{Lit, Var};
+
+use crate::backend;
+use crate::backend::{Backend, Statistics};
+use crate::graph::SolverFailure;
+
+/// A small, dependency-free CDCL solver, available with the `cdcl` feature.
+///
+/// It uses two watched literals, first-UIP clause learning, activity-based branching with phase saving, and Luby restarts.
+/// It keeps every learned clause, so it suits the modest instances produced by typical boards rather than industrial problems.
+#[derive(Clone, Debug)]
+pub struct Cdcl {
+    clauses: Vec<Vec<Lit>>,
+    // indexed by literal code, the clauses watching that literal
+    watches: Vec<Vec<usize>>,
+    // indexed by variable
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    activity: Vec<f64>,
+    phases: Vec<bool>,
+    seen: Vec<bool>,
+    activity_increment: f64,
+    trail: Vec<Lit>,
+    // where each decision level starts on the trail
+    trail_limits: Vec<usize>,
+    propagated: usize,
+    // the clauses alone have no model
+    contradicted: bool,
+    assumptions: Vec<Lit>,
+    model: Option<Vec<Lit>>,
+    failed_core: Option<Vec<Lit>>,
+    statistics: Statistics,
+}
+
+impl Default for Cdcl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The `index`th element of the Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, ...
+fn luby(mut index: usize) -> usize {
+    let mut size = 1;
+    let mut power = 1;
+    while size < index + 1 {
+        size = 2 * size + 1;
+        power *= 2;
+    }
+    while size - 1 != index {
+        size = (size - 1) / 2;
+        power /= 2;
+        index %= size;
+    }
+    power
+}
+
+impl Cdcl {
+    /// Create an empty solver.
+    pub fn new() -> Self {
+        Self {
+            clauses: Vec::new(),
+            watches: Vec::new(),
+            values: Vec::new(),
+            levels: Vec::new(),
+            reasons: Vec::new(),
+            activity: Vec::new(),
+            phases: Vec::new(),
+            seen: Vec::new(),
+            activity_increment: 1.0,
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            propagated: 0,
+            contradicted: false,
+            assumptions: Vec::new(),
+            model: None,
+            failed_core: None,
+            statistics: Statistics { decisions: Some(0), ..Default::default() },
+        }
+    }
+
+    #[inline]
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.index()].map(|value| value == lit.is_positive())
+    }
+
+    #[inline]
+    fn decision_level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    fn ensure_var(&mut self, var: Var) {
+        let count = var.index() + 1;
+        if self.values.len() < count {
+            self.values.resize(count, None);
+            self.levels.resize(count, 0);
+            self.reasons.resize(count, None);
+            self.activity.resize(count, 0.0);
+            self.phases.resize(count, false);
+            self.seen.resize(count, false);
+            self.watches.resize(2 * count, Vec::new());
+        }
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.index();
+        self.values[var] = Some(lit.is_positive());
+        self.levels[var] = self.decision_level();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+
+        for lit in self.trail.drain(self.trail_limits[level]..) {
+            self.phases[lit.index()] = lit.is_positive();
+            self.values[lit.index()] = None;
+            self.reasons[lit.index()] = None;
+        }
+        self.trail_limits.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    /// Watch the first two literals of a clause with at least two literals, returning its index.
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0].code()].push(index);
+        self.watches[clause[1].code()].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    /// Propagate every assignment on the trail, returning a conflicting clause if one arises.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let falsified = !self.trail[self.propagated];
+            self.propagated += 1;
+
+            let mut watching = std::mem::take(&mut self.watches[falsified.code()]);
+            let mut kept = 0;
+            let mut conflict = None;
+
+            let mut next = 0;
+            while next < watching.len() {
+                let index = watching[next];
+                next += 1;
+
+                // keep the falsified literal second
+                if self.clauses[index][0] == falsified {
+                    self.clauses[index].swap(0, 1);
+                }
+                let first = self.clauses[index][0];
+
+                if self.value(first) == Some(true) {
+                    watching[kept] = index;
+                    kept += 1;
+                    continue;
+                }
+
+                // look for another literal to watch
+                let replacement = (2..self.clauses[index].len()).find(|k| self.value(self.clauses[index][*k]) != Some(false));
+                if let Some(k) = replacement {
+                    self.clauses[index].swap(1, k);
+                    self.watches[self.clauses[index][1].code()].push(index);
+                    continue;
+                }
+
+                watching[kept] = index;
+                kept += 1;
+
+                if self.value(first) == Some(false) {
+                    conflict = Some(index);
+                    // keep the rest of the watches as they were
+                    while next < watching.len() {
+                        watching[kept] = watching[next];
+                        kept += 1;
+                        next += 1;
+                    }
+                } else {
+                    self.enqueue(first, Some(index));
+                }
+            }
+
+            watching.truncate(kept);
+            self.watches[falsified.code()] = watching;
+
+            if conflict.is_some() {
+                self.propagated = self.trail.len();
+                return conflict;
+            }
+        }
+
+        None
+    }
+
+    fn bump(&mut self, var: usize) {
+        self.activity[var] += self.activity_increment;
+        if self.activity[var] > 1e100 {
+            self.activity.iter_mut().for_each(|activity| *activity *= 1e-100);
+            self.activity_increment *= 1e-100;
+        }
+    }
+
+    /// Derive a clause from `conflict` with exactly one literal at the current level, returning it along with the level to backtrack to.
+    ///
+    /// The first literal of the clause is the one at the current level.
+    fn analyze(&mut self, mut conflict: usize) -> (Vec<Lit>, usize) {
+        let mut learned = vec![Lit::from_code(0)];
+        let mut pending = 0;
+        let mut implied: Option<Lit> = None;
+        let mut position = self.trail.len();
+
+        loop {
+            // the first literal of a reason is the one it implied
+            let skip = if implied.is_some() { 1 } else { 0 };
+            for k in skip..self.clauses[conflict].len() {
+                let lit = self.clauses[conflict][k];
+                let var = lit.index();
+                if self.seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+
+                self.seen[var] = true;
+                self.bump(var);
+                if self.levels[var] == self.decision_level() {
+                    pending += 1;
+                } else {
+                    learned.push(lit);
+                }
+            }
+
+            // walk back along the trail to the next literal involved
+            loop {
+                position -= 1;
+                if self.seen[self.trail[position].index()] {
+                    break;
+                }
+            }
+            let lit = self.trail[position];
+            self.seen[lit.index()] = false;
+            implied = Some(lit);
+            pending -= 1;
+
+            if pending == 0 {
+                learned[0] = !lit;
+                break;
+            }
+            conflict = self.reasons[lit.index()].unwrap();
+        }
+
+        learned.iter().skip(1).for_each(|lit| self.seen[lit.index()] = false);
+        self.activity_increment /= 0.95;
+
+        // watch the literal from the highest remaining level second, so the clause is correctly watched after backtracking
+        let mut level = 0;
+        if learned.len() > 1 {
+            let highest = (1..learned.len()).max_by_key(|k| self.levels[learned[*k].index()]).unwrap();
+            learned.swap(1, highest);
+            level = self.levels[learned[1].index()];
+        }
+
+        (learned, level)
+    }
+
+    /// Find the assumptions responsible for `lit`, which is the negation of some assumption, being true.
+    fn analyze_final(&mut self, lit: Lit) -> Vec<Lit> {
+        let mut core = vec![!lit];
+        if self.decision_level() == 0 {
+            return core;
+        }
+
+        self.seen[lit.index()] = true;
+        for position in (self.trail_limits[0]..self.trail.len()).rev() {
+            let trail_lit = self.trail[position];
+            let var = trail_lit.index();
+            if !self.seen[var] {
+                continue;
+            }
+
+            match self.reasons[var] {
+                // every decision so far is an assumption
+                None => core.push(trail_lit),
+                Some(reason) => {
+                    for k in 1..self.clauses[reason].len() {
+                        let var = self.clauses[reason][k].index();
+                        if self.levels[var] > 0 {
+                            self.seen[var] = true;
+                        }
+                    }
+                }
+            }
+            self.seen[var] = false;
+        }
+        self.seen[lit.index()] = false;
+
+        core
+    }
+
+    /// Pick the unassigned variable with the highest activity, or [`None`] if every variable is assigned.
+    fn pick_branch(&self) -> Option<Lit> {
+        (0..self.values.len())
+            .filter(|var| self.values[*var].is_none())
+            .max_by(|a, b| self.activity[*a].total_cmp(&self.activity[*b]).then(b.cmp(a)))
+            .map(|var| Var::from_index(var).lit(self.phases[var]))
+    }
+
+    /// Search until a model is found, a contradiction is derived, or `budget` conflicts have passed, or fail once the limits of the solve are exceeded.
+    fn search(&mut self, budget: usize) -> Result<Option<bool>, SolverFailure> {
+        let mut conflicts = 0;
+
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.statistics.conflicts += 1;
+                if self.decision_level() == 0 {
+                    self.contradicted = true;
+                    self.failed_core = Some(Vec::new());
+                    return Ok(Some(false));
+                }
+                backend::conflict()?;
+
+                conflicts += 1;
+                let (learned, level) = self.analyze(conflict);
+                self.backtrack(level);
+                if learned.len() == 1 {
+                    self.enqueue(learned[0], None);
+                } else {
+                    let asserting = learned[0];
+                    let index = self.attach(learned);
+                    self.enqueue(asserting, Some(index));
+                }
+                continue;
+            }
+
+            if conflicts >= budget {
+                self.backtrack(0);
+                return Ok(None);
+            }
+
+            // assumptions are decided first, one per level
+            let mut decision = None;
+            while self.decision_level() < self.assumptions.len() {
+                let assumption = self.assumptions[self.decision_level()];
+                match self.value(assumption) {
+                    Some(true) => self.trail_limits.push(self.trail.len()),
+                    Some(false) => {
+                        self.failed_core = Some(self.analyze_final(!assumption));
+                        return Ok(Some(false));
+                    }
+                    None => {
+                        decision = Some(assumption);
+                        break;
+                    }
+                }
+            }
+
+            let decision = match decision.or_else(|| self.pick_branch()) {
+                Some(decision) => decision,
+                None => {
+                    self.model = Some(self.values.iter()
+                        .enumerate()
+                        .map(|(var, value)| Var::from_index(var).lit(value.unwrap()))
+                        .collect());
+                    return Ok(Some(true));
+                }
+            };
+
+            self.statistics.decisions = self.statistics.decisions.map(|decisions| decisions + 1);
+            self.trail_limits.push(self.trail.len());
+            self.enqueue(decision, None);
+        }
+    }
+}
+
+impl Backend for Cdcl {
+    fn add_clause(&mut self, clause: &[Lit]) {
+        if self.contradicted {
+            return;
+        }
+        clause.iter().for_each(|lit| self.ensure_var(lit.var()));
+        self.backtrack(0);
+
+        let mut clause = clause.to_vec();
+        clause.sort();
+        clause.dedup();
+        // a clause containing both a literal and its negation always holds, as does one containing a literal fixed true
+        if clause.windows(2).any(|pair| pair[0] == !pair[1]) || clause.iter().any(|lit| self.value(*lit) == Some(true)) {
+            return;
+        }
+        clause.retain(|lit| self.value(*lit).is_none());
+
+        match clause.len() {
+            0 => self.contradicted = true,
+            1 => self.enqueue(clause[0], None),
+            _ => {
+                self.attach(clause);
+            }
+        }
+    }
+
+    fn assume(&mut self, assumptions: &[Lit]) {
+        assumptions.iter().for_each(|lit| self.ensure_var(lit.var()));
+        self.assumptions = assumptions.to_vec();
+    }
+
+    fn solve(&mut self) -> Result<bool, SolverFailure> {
+        self.model = None;
+        self.failed_core = None;
+        if self.contradicted {
+            self.failed_core = Some(Vec::new());
+            return Ok(false);
+        }
+
+        let mut restarts = 0;
+        let satisfiable = loop {
+            match self.search(100 * luby(restarts)) {
+                Ok(Some(satisfiable)) => break Ok(satisfiable),
+                Ok(None) => restarts += 1,
+                Err(reason) => break Err(reason),
+            }
+        };
+
+        self.backtrack(0);
+        satisfiable
+    }
+
+    fn model(&self) -> Option<Vec<Lit>> {
+        self.model.clone()
+    }
+
+    fn failed_core(&self) -> Option<Vec<Lit>> {
+        self.failed_core.clone()
+    }
+
+    fn statistics(&self) -> Option<Statistics> {
+        Some(self.statistics)
+    }
+}
@@ -1,24 +1,56 @@
-use std::fmt::{Display, Formatter};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display, Formatter};
+#[cfg(feature = "sat")]
+use std::io::BufRead;
 use std::num::NonZero;
 
+use itertools::Itertools;
 use petgraph::graphmap::UnGraphMap;
+#[cfg(any(feature = "sat", feature = "search"))]
 use petgraph::prelude::GraphMap;
+#[cfg(feature = "sat")]
+use petgraph::visit::Bfs;
+#[cfg(all(feature = "sat", feature = "parallel"))]
+use rayon::prelude::*;
 use unordered_pair::UnorderedPair;
+#[cfg(feature = "sat")]
+use varisat::Solver;
 
-use crate::affiliation::AffiliationID;
+use crate::affiliation::{affiliation_of, AffiliationID};
+#[cfg(feature = "sat")]
+use crate::backend::{self, Backend};
+use crate::builder::{Feature, SelfTouch};
 use crate::cell::{Cell, FrozenCellType};
+use crate::graph::{Condition, Drawn, Guards, HasAffiliation, Solution, Terminus};
+#[cfg(any(feature = "sat", feature = "search"))]
+use crate::graph::SolverFailure;
+#[cfg(feature = "sat")]
+use crate::graph::Uniqueness;
 use crate::location::{Dimension, Location};
+#[cfg(feature = "sat")]
+use crate::options::SolveOptions;
+use crate::propagation::{propagate, Deduction, Fact, Forced};
+#[cfg(feature = "sat")]
+use crate::rating::{Progress, Rater, Rating, Technique, Traced};
+#[cfg(feature = "search")]
+use crate::search::GraphSearch;
 use crate::shape::FullShape;
+#[cfg(feature = "sat")]
+use crate::shape::{exit_direction, MaybeSync};
+#[cfg(all(feature = "sat", feature = "parallel"))]
 use crate::solver;
-use crate::solver::{GraphSolver, SolverFailure, Terminus};
+#[cfg(feature = "sat")]
+use crate::solver::{GraphSolver, Straight};
+#[cfg(feature = "sat")]
+use crate::trace::{Reason, Step};
 
-#[derive(Copy, Clone, Hash, PartialEq, Eq, Ord, PartialOrd)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Ord, PartialOrd)]
 pub(crate) struct Node<Sh: FullShape> {
     pub(crate) location: Location,
     pub(crate) cell: Cell<Sh>,
 }
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub(crate) struct Edge<Sh>
 where
     Sh: FullShape,
@@ -40,23 +72,82 @@ where
     }
 }
 
-#[derive(Eq, PartialEq, Clone, Copy, Hash)]
-enum HasAffiliation<Sh> {
-    Node { location: Location },
-    Edge { nodes: UnorderedPair<Location>, direction: Sh },
+impl<Sh> Drawn for Node<Sh>
+where
+    Sh: FullShape,
+{
+    fn drawn(&self) -> Option<NonZero<AffiliationID>> {
+        match self.cell {
+            Cell::Path { affiliation } => NonZero::new(affiliation),
+            _ => None
+        }
+    }
 }
 
-impl<Sh: FullShape> From<&(Node<Sh>, Node<Sh>, &Edge<Sh>)> for HasAffiliation<Sh>
+impl<Sh> Drawn for Edge<Sh>
+where
+    Sh: FullShape,
+{
+    fn drawn(&self) -> Option<NonZero<AffiliationID>> {
+        NonZero::new(self.affiliation)
+    }
+}
+
+#[cfg(feature = "sat")]
+impl<Sh> Straight for Edge<Sh>
+where
+    Sh: FullShape,
 {
-    fn from(value: &(Node<Sh>, Node<Sh>, &Edge<Sh>)) -> Self {
-        Self::Edge { nodes: UnorderedPair::from((value.0.location, value.1.location)), direction: value.2.direction }
+    fn straight(&self, other: &Self) -> bool {
+        // warps run backward, so compare axes
+        self.direction.ensure_forward() == other.direction.ensure_forward()
     }
 }
 
-impl<Sh: FullShape> From<Node<Sh>> for HasAffiliation<Sh>
+/// A step towards solving a board which is correct in every solution, as given by [`Board::hint`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// The path displayed as `display` passes between these locations.
+    Segment {
+        /// The character identifying the path.
+        display: char,
+        /// The locations the path passes between.
+        locations: UnorderedPair<Location>,
+    },
+    /// The path displayed as `display` passes through this location.
+    Cell {
+        /// The character identifying the path.
+        display: char,
+        /// The location the path passes through.
+        location: Location,
+    },
+}
+
+/// What every solution of a board agrees on at one location, as found by [`Board::backbone`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settled<Sh>
+where
+    Sh: FullShape,
 {
-    fn from(value: Node<Sh>) -> Self {
-        Self::Node { location: value.location }
+    /// The display character of the path through this location, if it is the same in every solution.
+    ///
+    /// A bridge carries more than one path, so this is always [`None`] for one; its exits still say which paths are settled.
+    pub display: Option<char>,
+    /// For each direction in which every solution agrees whether a path leaves this location, whether it does.
+    pub exits: HashMap<Sh, bool>,
+}
+
+/// Direct every edge of `graph` which `feature` directs, as in [`Feature::directed`], while `condition` holds.
+pub(crate) fn direct<Sh: FullShape>(graph: &UnGraphMap<Node<Sh>, Edge<Sh>>, guards: &mut Guards<Node<Sh>, Edge<Sh>>, feature: &Feature, condition: Condition) {
+    let (directed, used) = feature.directed();
+    for (from, to) in directed {
+        for (n1, n2, e) in graph.all_edges().filter(|(n1, n2, _)| UnorderedPair(n1.location, n2.location) == UnorderedPair(from, to)) {
+            let edge = HasAffiliation::from_edge((n1, n2, e));
+            guards.directed.entry(edge).or_default().push((if n1.location == from { n1 } else { n2 }, condition.clone()));
+            if used {
+                guards.used.entry(edge).or_default().push(condition.clone());
+            }
+        }
     }
 }
 
@@ -64,6 +155,7 @@ impl<Sh: FullShape> From<Node<Sh>> for HasAffiliation<Sh>
 /// See the [`FullShape`] and [`Step`](crate::shape::Shape) traits for more information.
 ///
 /// [`Board`]s should be built using a [`Builder`](crate::builder::Builder) such as [`SquareBoardBuilder`](crate::builder::SquareBoardBuilder).
+#[derive(Clone)]
 pub struct Board<Sh>
 where
     Sh: FullShape,
@@ -71,6 +163,112 @@ where
     pub(crate) graph: UnGraphMap<Node<Sh>, Edge<Sh>>,
     pub(crate) dims: (Dimension, Dimension),
     pub(crate) affiliation_displays: Vec<char>,
+    pub(crate) features: Features<Sh>,
+}
+
+/// Every feature placed by a builder, along with a graph containing everything they could add and guards under which each can be left out.
+///
+/// The selector for each feature in `guards` has the same index as the feature in `list`.
+/// The rules on paths touching themselves, which are not features, come along too.
+#[derive(Clone)]
+pub(crate) struct Features<Sh>
+where
+    Sh: FullShape,
+{
+    pub(crate) list: Vec<Feature>,
+    pub(crate) superset: UnGraphMap<Node<Sh>, Edge<Sh>>,
+    pub(crate) guards: Guards<Node<Sh>, Edge<Sh>>,
+    pub(crate) self_touch: SelfTouch,
+}
+
+impl<Sh> Features<Sh>
+where
+    Sh: FullShape,
+{
+    /// Create a solver for [`Self::superset`] under these guards and rules.
+    #[cfg(feature = "sat")]
+    pub(crate) fn solver(&self) -> GraphSolver<'_, Node<Sh>, Edge<Sh>> {
+        GraphSolver::with_guards(&self.superset, self.guards.clone()).with_self_touch(self.self_touch)
+    }
+
+    /// Build the graph of the board with each feature switched on or off according to its selector, keeping only what remains.
+    ///
+    /// A terminus whose pair is switched off becomes an empty cell, as does a drawn cell once any segment drawn through it is switched off; such a segment is no longer drawn.
+    /// If `solution` is given, which must be a solution of [`Self::superset`] under these selectors, every empty cell and every edge is affiliated according to it.
+    pub(crate) fn graph(&self, solution: Option<&Solution<Node<Sh>, Edge<Sh>>>) -> UnGraphMap<Node<Sh>, Edge<Sh>> {
+        let Self { superset, guards, .. } = self;
+        let mut graph = UnGraphMap::with_capacity(superset.node_count(), superset.edge_count());
+        // nodes are keyed by their cell, so remember where each one went
+        let mut new_nodes = HashMap::with_capacity(superset.node_count());
+
+        for node in superset.nodes().filter(|node| guards.holds(guards.nodes.get(node))) {
+            let mut new_node = node;
+            if node.is_terminus().is_some() && !guards.holds(guards.termini.get(&node)) {
+                new_node.cell = Cell::Empty;
+            }
+            if node.drawn().is_some() && !guards.holds(guards.drawn.get(&HasAffiliation::from_node(node))) {
+                new_node.cell = Cell::Empty;
+            }
+            if new_node.cell == Cell::Empty {
+                if let Some(&affiliation) = solution.and_then(|solution| solution.get(&HasAffiliation::from_node(node))) {
+                    // a cell which need not be filled may be left empty
+                    if affiliation != 0 {
+                        new_node.cell = Cell::Path { affiliation };
+                    }
+                }
+            }
+
+            graph.add_node(new_node);
+            new_nodes.insert(node, new_node);
+        }
+
+        for triple in superset.all_edges() {
+            let (n1, n2, e) = triple;
+            if !(new_nodes.contains_key(&n1) && new_nodes.contains_key(&n2) && guards.holds(guards.edges.get(&HasAffiliation::from_edge(triple)))) {
+                continue;
+            }
+
+            let mut new_e = *e;
+            if !guards.holds(guards.drawn.get(&HasAffiliation::from_edge(triple))) {
+                new_e.affiliation = 0;
+            }
+            if let Some(solution) = solution {
+                new_e.affiliation = solution[&HasAffiliation::from_edge(triple)];
+            }
+            graph.add_edge(new_nodes[&n1], new_nodes[&n2], new_e);
+        }
+
+        graph
+    }
+}
+
+/// A node or edge of a board's graph, as the solver knows it.
+type Key<Sh> = HasAffiliation<Node<Sh>, Edge<Sh>>;
+
+/// One connected component of a board's graph, with its colours renumbered so that it can be solved on its own.
+#[cfg(feature = "sat")]
+pub(crate) struct Component<Sh>
+where
+    Sh: FullShape,
+{
+    graph: UnGraphMap<Node<Sh>, Edge<Sh>>,
+    // which nodes of `graph` may be left empty
+    guards: Guards<Node<Sh>, Edge<Sh>>,
+    // each node and edge of the board in this component, with the one standing for it in `graph`
+    keys: Vec<(Key<Sh>, Key<Sh>)>,
+    // the board's affiliation for each affiliation of `graph`, beginning with 0
+    affiliations: Vec<AffiliationID>,
+}
+
+#[cfg(feature = "sat")]
+impl<Sh> Component<Sh>
+where
+    Sh: FullShape,
+{
+    /// Express `solution`, a solution of [`Self::graph`], in terms of the board's nodes, edges and affiliations.
+    fn restore(&self, solution: &Solution<Node<Sh>, Edge<Sh>>) -> Solution<Node<Sh>, Edge<Sh>> {
+        self.keys.iter().map(|(key, new_key)| (*key, self.affiliations[solution[new_key]])).collect()
+    }
 }
 
 impl<Sh> Board<Sh>
@@ -78,47 +276,737 @@ where
     Sh: FullShape,
 {
     /// Solves this board, deferring to a [`GraphSolver`](crate::solver::GraphSolver) and mutating and returning `self` accordingly.
+    /// `options` chooses how the board is encoded; [`SolveOptions::default`] suits most boards.
+    ///
+    /// Each connected component of the board is solved on its own, with only the colours whose termini are inside it; with the `parallel` feature, they are solved in parallel.
+    /// A colour whose termini are in different components is explained at once, without solving any component, by a [`SolverFailure::Conflict`] blaming those termini and whatever keeps them apart.
+    /// Each component is solved by a portfolio of solvers if [`SolveOptions::portfolio`] asks for one.
+    /// With an [`objective`](SolveOptions::objective), each component is solved as well as it can be, which makes the whole board optimal too.
+    ///
+    /// Returns according to the result of [`GraphSolver::solve`](crate::solver::GraphSolver::solve), except that an unsolvable board is explained by [`SolverFailure::Conflict`].
+    #[cfg(feature = "sat")]
+    pub fn solve(self, options: SolveOptions) -> Result<Self, SolverFailure> {
+        self.solve_with_backend(options, Solver::new)
+    }
+
+    /// Solves this board as in [`Self::solve`], but using SAT solvers of the caller's choosing.
+    ///
+    /// `new_backend` creates an empty [`Backend`] whenever a SAT instance is needed; e.g. `Cdcl::new` or `|| DimacsProcess::new("kissat")`.
+    /// More than one is needed to solve each connected component of the board, or to explain an unsolvable board.
+    #[cfg(feature = "sat")]
+    pub fn solve_with_backend<B: Backend>(mut self, options: SolveOptions, new_backend: impl Fn() -> B + MaybeSync) -> Result<Self, SolverFailure> {
+        // explaining an unsolvable board counts against the same limits
+        let _limits = backend::watch(&options);
+        let limits = backend::watched();
+        let solve = |component: &Component<Sh>| {
+            // components solved on other threads count against the same limits
+            let _limits = backend::adopt(limits.clone());
+            // propagation assumes every cell is filled and no path touches itself
+            let forced = match options.propagate && component.guards.filled.is_empty() && self.features.self_touch.orthogonal {
+                true => propagate(&component.graph),
+                false => Vec::new(),
+            };
+            let solve = |options: SolveOptions| {
+                let objective = options.objective;
+                let solver = GraphSolver::with_guards(&component.graph, component.guards.clone())
+                    .with_self_touch(self.features.self_touch)
+                    .with_options(options)
+                    .with_forced(forced.clone());
+                match objective {
+                    None => solver.solve(new_backend()),
+                    Some(objective) => solver.minimize(new_backend(), objective),
+                }
+            };
+            #[cfg(feature = "parallel")]
+            let solution = match options.portfolio {
+                0 | 1 => solve(options.clone()),
+                _ => solver::race(&options, solve),
+            };
+            #[cfg(not(feature = "parallel"))]
+            let solution = solve(options.clone());
+            solution.map(|solution| component.restore(&solution))
+        };
+
+        // a board which cannot be split into components is already explained
+        let components = self.components()?;
+        #[cfg(feature = "parallel")]
+        let solutions = components.par_iter().map(solve).collect::<Result<Vec<_>, _>>();
+        #[cfg(not(feature = "parallel"))]
+        let solutions = components.iter().map(solve).collect::<Result<Vec<_>, _>>();
+        let solution: Solution<_, _> = match solutions {
+            Err(SolverFailure::Inconsistent) => return Err(self.explain(&options, new_backend)),
+            result => result?.into_iter().flatten().collect(),
+        };
+
+        self.graph = self.solved_graph(&solution);
+        Ok(self)
+    }
+
+    /// Solves this board by searching for paths directly, without a SAT solver; available with the `search` feature.
+    ///
+    /// For a board with exactly one solution, the result is the same as from [`Self::solve`], but an unsolvable board is only ever [`SolverFailure::Inconsistent`].
+    /// The search backtracks, so it suits the small and medium boards of typical puzzles; see [`GraphSearch`](crate::search::GraphSearch).
+    #[cfg(feature = "search")]
+    pub fn solve_by_search(mut self) -> Result<Self, SolverFailure> {
+        let solution = GraphSearch::from(&self.graph)
+            .with_guards(&self.guards(&self.graph, Some))
+            .with_self_touch(self.features.self_touch)
+            .solve()?;
+
+        self.graph = self.solved_graph(&solution);
+        Ok(self)
+    }
+
+    /// Finds what simple local rules force in every solution of this board, without consuming it.
+    ///
+    /// [`Self::solve`] does the same by default and assumes the results; see [`SolveOptions::propagate`].
+    /// The deductions are listed in the order they were made, so each follows from the board and those before it.
+    /// The rules assume every cell is filled and no path touches itself, so nothing is found if cells may be left empty or paths may touch; see [`Builder::allow_empty`](crate::Builder::allow_empty) and [`Builder::self_touch`](crate::Builder::self_touch).
+    pub fn propagate(&self) -> Vec<Deduction> {
+        if !self.techniques_hold() {
+            return Vec::new();
+        }
+
+        propagate(&self.graph).into_iter()
+            .map(|forced| self.deduction(forced))
+            .collect()
+    }
+
+    /// Encodes this board in DIMACS CNF format, for solving with an external SAT solver.
+    ///
+    /// The clauses are exactly those from which [`Self::solve`] starts, and a comment header maps each variable back to a vertex or edge and an affiliation.
+    /// See [`GraphSolver::write_dimacs`](crate::solver::GraphSolver::write_dimacs) for details.
+    #[cfg(feature = "sat")]
+    pub fn to_dimacs(&self) -> String
+    where
+        Sh: Debug,
+    {
+        let mut out = Vec::new();
+        self.solver().write_dimacs(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Solves this board using a model of the formula from [`Self::to_dimacs`], as written by an external SAT solver and read from `model`.
+    ///
+    /// Returns according to the result of [`GraphSolver::read_model`](crate::solver::GraphSolver::read_model).
+    #[cfg(feature = "sat")]
+    pub fn solve_from_dimacs_model(mut self, model: impl BufRead) -> Result<Self, SolverFailure> {
+        let solution = self.solver().read_model(model)?;
+
+        self.graph = self.solved_graph(&solution);
+        Ok(self)
+    }
+
+    /// Determines whether this board has exactly one solution, without consuming it.
+    ///
+    /// If the board has multiple solutions, two of them are returned so they can be compared.
+    /// Returns according to the result of [`GraphSolver::check_unique`](crate::solver::GraphSolver::check_unique).
+    #[cfg(feature = "sat")]
+    pub fn check_unique(&self) -> Result<Uniqueness<Self>, SolverFailure> {
+        let solver = self.solver();
+
+        Ok(match solver.check_unique()? {
+            Uniqueness::Unique(solution) => Uniqueness::Unique(self.with_solution(&solution)),
+            Uniqueness::Multiple(a, b) => Uniqueness::Multiple(self.with_solution(&a), self.with_solution(&b)),
+            Uniqueness::Unsolvable => Uniqueness::Unsolvable,
+        })
+    }
+
+    /// Lazily enumerates the solutions of this board, without consuming it.
+    /// At most `cap` solutions are produced if it is given.
+    ///
+    /// Solutions are distinguished by their paths, so no two yielded boards display identically.
+    /// See [`GraphSolver::solutions`](crate::solver::GraphSolver::solutions) for details.
+    #[cfg(feature = "sat")]
+    pub fn solutions(&self, cap: Option<usize>) -> impl Iterator<Item=Result<Self, SolverFailure>> + '_ {
+        self.solver()
+            .solutions(cap)
+            .map(|solution| solution.map(|solution| self.with_solution(&solution)))
+    }
+
+    /// Finds one step towards solving this board which is correct in every solution and not yet drawn, without consuming it.
+    ///
+    /// A segment joining a drawn path or terminus to its next cell is preferred, then any other segment, then a cell alone, which may be all that is forced on a board with many solutions.
+    /// Returns [`None`] if nothing more is forced, such as on a solved board.
+    /// If the board is unsolvable, fails as [`Self::solve`] does; in particular, drawn segments which cannot all be part of a solution are named by [`SolverFailure::Conflict`].
+    #[cfg(feature = "sat")]
+    pub fn hint(&self) -> Result<Option<Hint>, SolverFailure> {
+        // a cell from which drawing can carry on
+        let anchored = |node: Node<Sh>| node.is_terminus().is_some() || node.drawn().is_some();
+
+        let candidates = |solution: &Solution<Node<Sh>, Edge<Sh>>| {
+            let segments = self.graph.all_edges()
+                .filter(|(_, _, e)| e.drawn().is_none())
+                .map(HasAffiliation::from_edge)
+                .filter(|subject| solution[subject] != 0)
+                .sorted_by_key(|subject| match subject {
+                    HasAffiliation::Edge { endpoints, .. } => !(anchored(endpoints.0) || anchored(endpoints.1)),
+                    HasAffiliation::Node { .. } => unreachable!(),
+                });
+            let cells = self.graph.nodes()
+                .filter(|node| node.cell == Cell::Empty)
+                .map(HasAffiliation::from_node)
+                .filter(|subject| solution[subject] != 0);
+
+            segments.chain(cells).collect_vec()
+        };
+
+        let forced = match self.solver().backbone(Solver::new(), candidates, Some(1))? {
+            None => return Err(self.explain(&SolveOptions::default(), Solver::new)),
+            Some(forced) => forced,
+        };
+
+        Ok(forced.first().map(|(subject, aff)| {
+            let display = self.affiliation_displays[*aff];
+            match subject {
+                HasAffiliation::Edge { endpoints, .. } => Hint::Segment { display, locations: UnorderedPair(endpoints.0.location, endpoints.1.location) },
+                HasAffiliation::Node { node } => Hint::Cell { display, location: node.location },
+            }
+        }))
+    }
+
+    /// Estimates how hard this board is for a person to solve, without consuming it.
     ///
-    /// Returns according to the result of [`GraphSolver::solve`](crate::solver::GraphSolver::solve).
-    pub fn solve(mut self) -> Result<Self, SolverFailure> {
-        let solver = GraphSolver::from(&self.graph);
-        let solution = solver.solve()?;
+    /// The board is solved with the catalogue of human [`Technique`](crate::rating::Technique)s, and its [`Rating`] breaks down which were needed and how often.
+    /// It is also solved once by SAT, without propagating first, but [`varisat`] keeps no counts, so [`Rating::sat`] is [`None`]; see [`Self::rate_with_backend`] to count them.
+    /// The techniques assume every cell is filled and no path touches itself, so if cells may be left empty or paths may touch, none is used and the rating is incomplete.
+    /// If the board is unsolvable, fails as [`Self::solve`] does.
+    #[cfg(feature = "sat")]
+    pub fn rate(&self) -> Result<Rating, SolverFailure> {
+        self.rate_with_backend(Solver::new)
+    }
+
+    /// Rates this board as in [`Self::rate`], but using SAT solvers of the caller's choosing, as in [`Self::solve_with_backend`].
+    ///
+    /// Only backends which keep [`statistics`](Backend::statistics), such as `Cdcl` from the `cdcl` feature or any wrapped in [`Counted`](backend::Counted), fill in [`Rating::sat`].
+    #[cfg(feature = "sat")]
+    pub fn rate_with_backend<B: Backend>(&self, new_backend: impl Fn() -> B) -> Result<Rating, SolverFailure> {
+        let solver = self.solver();
+        let mut backend = new_backend();
+        solver.load(&mut backend);
+        if solver.next_solution(&mut backend)?.is_none() {
+            return Err(self.explain(&SolveOptions::default(), new_backend));
+        }
 
+        Ok(Rating {
+            sat: backend.statistics(),
+            ..self.rater().rate()
+        })
+    }
+
+    /// Explains, step by step, how a person could solve this board, without consuming it.
+    ///
+    /// Each [`Step`] applies the simplest [`Technique`](crate::rating::Technique) which makes progress, as in [`Self::rate`], and says which [`Reason`] its facts follow from.
+    /// Where no technique makes progress, a connection is taken from a solution and marked as [`Reason::Guess`], so the steps always reach a solution; it is the same as [`Self::solve`] gives for a board with exactly one.
+    /// If cells may be left empty or paths may touch, no technique applies, so every step is a guess.
+    /// If the board is unsolvable, fails as [`Self::solve`] does.
+    #[cfg(feature = "sat")]
+    pub fn trace(&self) -> Result<Vec<Step>, SolverFailure> {
+        let solution = match self.solver().solve(Solver::new()) {
+            Err(SolverFailure::Inconsistent) => return Err(self.explain(&SolveOptions::default(), Solver::new)),
+            result => result?,
+        };
+
+        // the cells filled so far, for printing the board after each step
+        let mut known = HashMap::new();
+        let mut steps = Vec::new();
+        let mut push = |reason, filled: &[(Node<Sh>, AffiliationID)], facts| {
+            known.extend(filled.iter().copied());
+            steps.push(Step { reason, facts, board: self.render(&self.known_graph(&known)) });
+        };
+
+        for traced in self.rater().trace(&solution) {
+            match traced {
+                Traced::Progress(Progress::Forced(forced)) => for forced in forced {
+                    let filled = match forced {
+                        Forced::Affiliated(_, node, aff) => vec![(node, aff)],
+                        _ => Vec::new(),
+                    };
+                    let Deduction { rule, fact } = self.deduction(forced);
+                    push(Reason::Rule(rule), &filled, vec![fact]);
+                },
+                Traced::Progress(Progress::Filled(technique, filled)) => {
+                    let reason = match technique {
+                        Technique::RegionParity => Reason::Region,
+                        _ => Reason::Bottleneck,
+                    };
+                    let facts = filled.iter().map(|(node, aff)| Fact::Affiliated(node.location, self.affiliation_displays[*aff])).collect();
+                    push(reason, &filled, facts);
+                }
+                Traced::Progress(Progress::Decided(edge, used, depth)) => push(Reason::Contradiction { depth }, &[], vec![Self::edge_fact(edge, used)]),
+                Traced::Guessed(edge, used) => push(Reason::Guess, &[], vec![Self::edge_fact(edge, used)]),
+            }
+        }
+
+        Ok(steps)
+    }
+
+    /// Lists, for each pair of termini switched on in the order they were added, its display character and its locations in the order its path travels between them.
+    ///
+    /// A path which crosses a one-way edge or an arrow, as drawn or once solved, travels the way they allow.
+    /// Any other path may travel either way, so it is given as travelling from the first location given for its termini to the second.
+    pub fn travel(&self) -> Vec<(char, (Location, Location))> {
+        let directed = self.guards(&self.graph, Some).directed;
+
+        self.termini_on()
+            .map(|(display, (l1, l2))| {
+                // look for an edge the path crosses against the way it may
+                let reversed = self.walk(display, l1).into_iter()
+                    .any(|(node, edge)| directed.get(&edge).is_some_and(|starts| starts.iter().any(|(from, _)| *from != node)));
+
+                (display, if reversed { (l2, l1) } else { (l1, l2) })
+            })
+            .collect()
+    }
+
+    /// Lists, for each pair of termini switched on in the order they were added, its display character and the number of cells its path passes through, as drawn or once solved.
+    ///
+    /// Both termini are counted, and a bridge is counted once for each time the path passes through it; see [`Builder::add_termini_with_length`](crate::Builder::add_termini_with_length).
+    pub fn lengths(&self) -> Vec<(char, usize)> {
+        self.termini_on()
+            .map(|(display, (l1, _))| (display, self.walk(display, l1).len() + 1))
+            .collect()
+    }
+
+    /// The display character and locations of each pair of termini switched on, in the order they were added.
+    fn termini_on(&self) -> impl Iterator<Item = (char, (Location, Location))> + '_ {
+        let Features { list, guards, .. } = &self.features;
+        list.iter().zip(&guards.selectors)
+            .filter_map(|(feature, on)| match feature {
+                Feature::Termini { display, locations } if *on => Some((*display, *locations)),
+                _ => None,
+            })
+    }
+
+    /// Walk the path displayed as `display` from its terminus at `location`, listing each edge it crosses along with the vertex it crosses it from.
+    fn walk(&self, display: char, location: Location) -> Vec<(Node<Sh>, Key<Sh>)> {
+        let start = self.graph.nodes()
+            .find(|node| node.location == location && node.is_terminus().is_some_and(|aff| self.affiliation_displays[aff.get()] == display));
+
+        let mut crossed = Vec::new();
+        let (mut previous, mut current) = (None, start);
+        while let Some(node) = current {
+            let next = self.graph.edges(node)
+                .map(|(n1, n2, e)| (if n1 == node { n2 } else { n1 }, HasAffiliation::from_edge((n1, n2, e)), e))
+                .find(|(other, _, e)| e.affiliation != 0 && Some(*other) != previous);
+            crossed.extend(next.map(|(_, edge, _)| (node, edge)));
+            (previous, current) = (Some(node), next.map(|(other, _, _)| other));
+        }
+
+        crossed
+    }
+
+    /// Finds, for every location on this board, what is the same in every solution, without consuming it.
+    ///
+    /// The result is the backbone of the underlying SAT instance: each affiliation is checked by assuming it differs, and each solution found along the way rules out everything it disagrees with.
+    /// A location settled everywhere, with a display character and every exit, is solved in the same way by every solution; a board is uniquely solvable if every location is.
+    /// If the board is unsolvable, fails as [`Self::solve`] does.
+    #[cfg(feature = "sat")]
+    pub fn backbone(&self) -> Result<HashMap<Location, Settled<Sh>>, SolverFailure> {
+        let candidates = |_: &Solution<Node<Sh>, Edge<Sh>>| self.graph.nodes()
+            .map(HasAffiliation::from_node)
+            .chain(self.graph.all_edges().map(HasAffiliation::from_edge))
+            .collect_vec();
+
+        let forced = match self.solver().backbone(Solver::new(), candidates, None)? {
+            None => return Err(self.explain(&SolveOptions::default(), Solver::new)),
+            Some(forced) => forced,
+        };
+
+        let mut settled: HashMap<Location, Settled<Sh>> = self.graph.nodes()
+            .map(|node| (node.location, Settled { display: None, exits: HashMap::new() }))
+            .collect();
+        for (subject, aff) in forced {
+            match subject {
+                HasAffiliation::Node { node } => if !matches!(node.cell, Cell::Bridge { .. }) {
+                    settled.get_mut(&node.location).unwrap().display = Some(self.affiliation_displays[aff]);
+                },
+                HasAffiliation::Edge { endpoints, edge } => for (this_node, neighbor) in [(endpoints.0, endpoints.1), (endpoints.1, endpoints.0)] {
+                    settled.get_mut(&this_node.location).unwrap().exits.insert(exit_direction(this_node, neighbor, &edge), aff != 0);
+                },
+            }
+        }
+
+        Ok(settled)
+    }
+
+    /// Find the features of this unsolvable board which are in conflict, or fall back to [`SolverFailure::Inconsistent`] if none can be found.
+    ///
+    /// If the board has drawn segments, they alone are blamed if possible, so that a wrong drawing is pointed out rather than the puzzle itself.
+    /// Otherwise, cells may first be left empty, in case the paths cannot be drawn at all; then, every cell must be filled again.
+    #[cfg(feature = "sat")]
+    pub(crate) fn explain<B: Backend>(&self, options: &SolveOptions, new_backend: impl Fn() -> B) -> SolverFailure {
+        let Features { list, superset, guards, self_touch } = &self.features;
+
+        let drawn = (0..list.len())
+            .filter(|selector| guards.selectors[*selector] && matches!(list[*selector], Feature::Drawn { .. }))
+            .collect_vec();
+        if !drawn.is_empty() {
+            match self.features.solver().with_options(options.clone()).conflicting_selectors(&drawn, new_backend()) {
+                // with no segment drawn, the board is still unsolvable
+                Ok(Some(selectors)) if selectors.is_empty() => {}
+                Ok(Some(selectors)) => return SolverFailure::Conflict(selectors.into_iter().map(|selector| list[selector].clone()).collect()),
+                Ok(None) => return SolverFailure::Inconsistent,
+                Err(reason) => return reason,
+            }
+        }
+
+        // features which are switched off stay that way
+        let (fills, others): (Vec<usize>, Vec<usize>) = (0..list.len())
+            .filter(|selector| guards.selectors[*selector])
+            .partition(|selector| matches!(list[*selector], Feature::Filled(_)));
+
+        let mut unfilled = guards.clone();
+        fills.iter().for_each(|selector| unfilled.selectors[*selector] = false);
+
+        let conflict = GraphSolver::with_guards(superset, unfilled)
+            .with_self_touch(*self_touch)
+            .with_options(options.clone())
+            .conflicting_selectors(&others, new_backend())
+            .and_then(|conflict| match conflict {
+                None => self.features.solver()
+                    .with_options(options.clone())
+                    .conflicting_selectors(&fills, new_backend()),
+                conflict => Ok(conflict),
+            });
+
+        match conflict {
+            Ok(Some(selectors)) => SolverFailure::Conflict(selectors.into_iter().map(|selector| list[selector].clone()).collect()),
+            Ok(None) => SolverFailure::Inconsistent,
+            Err(reason) => reason,
+        }
+    }
+
+    /// Split this board's graph into its connected components, each keeping only the colours whose termini are inside it.
+    ///
+    /// Fails with [`SolverFailure::Conflict`], without solving anything, if the termini of some colour are split between components, blaming those termini and the walls, holes and bridges between them,
+    /// or if a segment is drawn or a checkpoint placed in a component without termini of its colour, blaming the segments and checkpoints there.
+    #[cfg(feature = "sat")]
+    pub(crate) fn components(&self) -> Result<Vec<Component<Sh>>, SolverFailure> {
+        let mut labels = HashMap::with_capacity(self.graph.node_count());
+        let mut count = 0;
+        for start in self.graph.nodes() {
+            if labels.contains_key(&start) {
+                continue;
+            }
+
+            let mut bfs = Bfs::new(&self.graph, start);
+            while let Some(node) = bfs.next(&self.graph) {
+                labels.insert(node, count);
+            }
+            count += 1;
+        }
+
+        let Features { list, superset, guards, .. } = &self.features;
+        let mut termini: HashMap<AffiliationID, usize> = HashMap::new();
+        for node in self.graph.nodes() {
+            let Some(affiliation) = node.is_terminus() else { continue };
+            let (first, second) = (*termini.entry(affiliation.get()).or_insert(labels[&node]), labels[&node]);
+            if first == second {
+                continue;
+            }
+
+            // blame the termini, listed in the order of their affiliations, then every wall, hole and bridge which keeps their components apart
+            let pair = list.iter().filter(|feature| matches!(feature, Feature::Termini { .. })).nth(affiliation.get() - 1);
+            let nodes_at = self.graph.nodes().into_group_map_by(|node| node.location);
+            let joins = |locations: Vec<Location>| {
+                let touched = locations.iter()
+                    .flat_map(|location| nodes_at.get(location).into_iter().flatten())
+                    .map(|node| labels[node])
+                    .collect::<HashSet<_>>();
+                touched.contains(&first) && touched.contains(&second)
+            };
+            let features = list.iter().zip(&guards.selectors)
+                .filter(|(_, on)| **on)
+                .map(|(feature, _)| feature)
+                .filter(|feature| match feature {
+                    Feature::Termini { .. } => pair == Some(*feature),
+                    Feature::Wall(locations) => joins(vec![locations.0, locations.1]),
+                    Feature::Bridge(location) => joins(vec![*location]),
+                    Feature::DroppedLocation(location) => joins(superset.nodes()
+                        .filter(|node| node.location == *location)
+                        .flat_map(|node| superset.neighbors(node))
+                        .map(|node| node.location)
+                        .collect()),
+                    _ => false,
+                })
+                .cloned()
+                .collect();
+            return Err(SolverFailure::Conflict(features));
+        }
+
+        // a segment drawn or checkpoint placed at `location` whose path has no termini in its component can never be reached
+        let stray = |location: Location, affiliation: AffiliationID| {
+            let display = self.affiliation_displays[affiliation];
+            let features = list.iter().zip(&guards.selectors)
+                .filter(|(feature, on)| **on && match feature {
+                    Feature::Drawn { display: other, locations } => *other == display && (locations.0 == location || locations.1 == location),
+                    Feature::Checkpoint { display: other, location: other_location } => *other == display && *other_location == location,
+                    _ => false,
+                })
+                .map(|(feature, _)| feature.clone())
+                .collect_vec();
+            match features.is_empty() {
+                true => SolverFailure::Inconsistent,
+                false => SolverFailure::Conflict(features),
+            }
+        };
+
+        let (_, required) = self.restrictions();
+        (0..count).map(|label| {
+            let nodes = self.graph.nodes().filter(|node| labels[node] == label).collect_vec();
+            let affiliations = std::iter::once(0)
+                .chain(nodes.iter().filter_map(|node| node.is_terminus()).map(NonZero::get).sorted().dedup())
+                .collect_vec();
+            let renumber = |affiliation: AffiliationID, location: Location| affiliations.binary_search(&affiliation).map_err(|_| stray(location, affiliation));
+
+            let mut component = Component { graph: UnGraphMap::new(), guards: Guards::default(), keys: Vec::new(), affiliations: Vec::new() };
+            let mut new_nodes = HashMap::with_capacity(nodes.len());
+            for node in nodes {
+                let cell = match node.cell {
+                    Cell::Terminus { affiliation } => Cell::Terminus { affiliation: renumber(affiliation, node.location)? },
+                    Cell::Path { affiliation } => Cell::Path { affiliation: renumber(affiliation, node.location)? },
+                    Cell::Bridge { affiliation, direction } => Cell::Bridge { affiliation: affiliation.map(|affiliation| renumber(affiliation, node.location)).transpose()?, direction },
+                    Cell::Empty => Cell::Empty,
+                };
+                let new_node = Node { location: node.location, cell };
+
+                component.graph.add_node(new_node);
+                component.keys.push((HasAffiliation::from_node(node), HasAffiliation::from_node(new_node)));
+                new_nodes.insert(node, new_node);
+            }
+
+            for (n1, n2, e) in self.graph.all_edges().filter(|(n1, _, _)| labels[n1] == label) {
+                let new_e = Edge { affiliation: renumber(e.affiliation, n1.location)?, ..*e };
+
+                component.graph.add_edge(new_nodes[&n1], new_nodes[&n2], new_e);
+                component.keys.push((HasAffiliation::from_edge((n1, n2, e)), HasAffiliation::from_edge((new_nodes[&n1], new_nodes[&n2], &new_e))));
+            }
+
+            // a checkpoint whose path has no termini here can never be passed
+            for node in component.graph.nodes() {
+                for aff in required.get(&node.location).into_iter().flatten() {
+                    renumber(*aff, node.location)?;
+                }
+            }
+
+            component.guards = self.guards(&component.graph, |affiliation| affiliations.binary_search(&affiliation).ok());
+            component.affiliations = affiliations;
+            Ok(component)
+        }).collect()
+    }
+
+    /// The locations of this board which may be left empty, their [`Feature::Filled`] being switched off.
+    fn optional(&self) -> HashSet<Location> {
+        let Features { list, guards, .. } = &self.features;
+        list.iter().zip(&guards.selectors)
+            .filter_map(|(feature, on)| match feature {
+                Feature::Filled(location) if !on => Some(*location),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The affiliations kept out of each location, and those required at it, by the restrictions and checkpoints switched on.
+    fn restrictions(&self) -> (HashMap<Location, Vec<AffiliationID>>, HashMap<Location, Vec<AffiliationID>>) {
+        let Features { list, guards, .. } = &self.features;
+        let mut forbidden: HashMap<Location, Vec<AffiliationID>> = HashMap::new();
+        let mut required: HashMap<Location, Vec<AffiliationID>> = HashMap::new();
+        // affiliation 0 displays as empty, so skip it
+        let affiliations = |allowed: bool, displays: &Vec<char>| self.affiliation_displays.iter()
+            .positions(move |display| displays.contains(display) != allowed)
+            .filter(|aff| *aff != 0)
+            .collect_vec();
+
+        for (feature, _) in list.iter().zip(&guards.selectors).filter(|(_, on)| **on) {
+            match feature {
+                Feature::Allowed { location, displays } => forbidden.entry(*location).or_default().extend(affiliations(true, displays)),
+                Feature::Forbidden { location, displays } => forbidden.entry(*location).or_default().extend(affiliations(false, displays)),
+                Feature::Checkpoint { display, location } => required.entry(*location).or_default()
+                    .extend(affiliation_of(&self.affiliation_displays[1..], *display)),
+                _ => {}
+            }
+        }
+
+        (forbidden, required)
+    }
+
+    /// Build [`Guards`] for `graph`, whose vertices are at locations of this board, under which every vertex at an [optional](Self::optional) location may be left empty
+    /// and the [restrictions](Self::restrictions), the directions of edges and the lengths of paths switched on hold, always.
+    ///
+    /// `renumber` gives the affiliation on `graph` of each affiliation of this board, if it has one; a restriction or length on an affiliation without one is dropped.
+    fn guards(&self, graph: &UnGraphMap<Node<Sh>, Edge<Sh>>, renumber: impl Fn(AffiliationID) -> Option<AffiliationID>) -> Guards<Node<Sh>, Edge<Sh>> {
+        let optional = self.optional();
+        let mut guards = Guards::default();
+
+        if !optional.is_empty() {
+            // a single selector, switched off, which every optional vertex needs to be filled
+            guards.selectors.push(false);
+            guards.filled = graph.nodes()
+                .filter(|node| optional.contains(&node.location))
+                .map(|node| (node, vec![(0, true)]))
+                .collect();
+        }
+
+        let (forbidden, required) = self.restrictions();
+        let restricted = |restrictions: &HashMap<Location, Vec<AffiliationID>>| graph.nodes()
+            .filter_map(|node| restrictions.get(&node.location).map(|affs| (node, affs.iter().filter_map(|aff| renumber(*aff)).map(|aff| (aff, Vec::new())).collect())))
+            .collect();
+        guards.forbidden = restricted(&forbidden);
+        guards.required = restricted(&required);
+
+        let Features { list, guards: features, .. } = &self.features;
+        for (selector, feature) in list.iter().enumerate().filter(|(selector, _)| features.selectors[*selector]) {
+            direct(graph, &mut guards, feature, Vec::new());
+
+            // a length applies to the termini the builder gave it to, found by its selector, while they are on `graph`
+            if let Feature::Length { length, .. } = feature {
+                let aff = features.lengths.iter()
+                    .find(|(_, lengths)| lengths.iter().any(|(_, condition)| condition.contains(&(selector, true))))
+                    .and_then(|(aff, _)| renumber(*aff));
+                if let Some(aff) = aff.filter(|aff| graph.nodes().any(|node| node.is_terminus().is_some_and(|other| other.get() == *aff))) {
+                    guards.lengths.entry(aff).or_default().push((length.clone(), Vec::new()));
+                }
+            }
+        }
+        guards
+    }
+
+    /// Create a solver for this board's graph, under which [optional](Self::optional) cells may be left empty and the [restrictions](Self::restrictions) hold.
+    #[cfg(feature = "sat")]
+    fn solver(&self) -> GraphSolver<'_, Node<Sh>, Edge<Sh>> {
+        GraphSolver::with_guards(&self.graph, self.guards(&self.graph, Some)).with_self_touch(self.features.self_touch)
+    }
+
+    /// Whether every cell must be filled and no path may touch itself, as propagation and the techniques of a [`Rating`] assume.
+    fn techniques_hold(&self) -> bool {
+        self.optional().is_empty() && self.features.self_touch.orthogonal
+    }
+
+    /// Create a rater for this board's graph, whose techniques only apply under the usual rules; see [`Self::techniques_hold`].
+    #[cfg(feature = "sat")]
+    fn rater(&self) -> Rater<'_, Node<Sh>, Edge<Sh>> {
+        let rater = Rater::from(&self.graph);
+        match self.techniques_hold() {
+            true => rater,
+            false => rater.guessing(),
+        }
+    }
+
+    /// Express a fact forced on this board's graph in terms of locations and display characters.
+    fn deduction(&self, forced: Forced<Node<Sh>, Edge<Sh>>) -> Deduction {
+        match forced {
+            Forced::Used(rule, edge) => Deduction { rule, fact: Self::edge_fact(edge, true) },
+            Forced::Unused(rule, edge) => Deduction { rule, fact: Self::edge_fact(edge, false) },
+            Forced::Affiliated(rule, node, aff) => Deduction { rule, fact: Fact::Affiliated(node.location, self.affiliation_displays[aff]) },
+        }
+    }
+
+    /// Express whether `edge` is used as a [`Fact`].
+    fn edge_fact(edge: HasAffiliation<Node<Sh>, Edge<Sh>>, used: bool) -> Fact {
+        let HasAffiliation::Edge { endpoints, .. } = edge else {
+            unreachable!()
+        };
+
+        let locations = UnorderedPair(endpoints.0.location, endpoints.1.location);
+        if used { Fact::Connected(locations) } else { Fact::Disconnected(locations) }
+    }
+
+    /// Build a copy of this board's graph, filling each empty cell whose affiliation is in `known`.
+    #[cfg(feature = "sat")]
+    fn known_graph(&self, known: &HashMap<Node<Sh>, AffiliationID>) -> UnGraphMap<Node<Sh>, Edge<Sh>> {
+        let mut graph = UnGraphMap::with_capacity(self.graph.node_count(), self.graph.edge_count());
+        // nodes are keyed by their cell, so remember where each one went
+        let mut new_nodes = HashMap::with_capacity(self.graph.node_count());
+        for node in self.graph.nodes() {
+            let mut new_node = node;
+            if let (Cell::Empty, Some(&affiliation)) = (node.cell, known.get(&node)) {
+                new_node.cell = Cell::Path { affiliation };
+            }
+
+            graph.add_node(new_node);
+            new_nodes.insert(node, new_node);
+        }
+
+        for (n1, n2, e) in self.graph.all_edges() {
+            graph.add_edge(new_nodes[&n1], new_nodes[&n2], *e);
+        }
+
+        graph
+    }
+
+    /// Print `graph`, which must have this board's shape and affiliations, as [`Display`] prints a board.
+    fn render(&self, graph: &UnGraphMap<Node<Sh>, Edge<Sh>>) -> String {
+        let mut rendered = Sh::print(Sh::gph_to_array(self.dims, graph).map(|cell| match cell.cell_type {
+            FrozenCellType::Terminus { affiliation } => self.affiliation_displays.get(affiliation.get()).unwrap().to_ascii_uppercase(),
+            FrozenCellType::Path { affiliation } => self.affiliation_displays.get(affiliation.get()).unwrap().to_ascii_lowercase(),
+            FrozenCellType::Bridge { .. } => '+',
+            FrozenCellType::Empty => '.',
+        }));
+
+        // no cell can show these
+        let Features { list, guards, .. } = &self.features;
+        for (feature, _) in list.iter().zip(&guards.selectors).filter(|(_, on)| **on) {
+            match feature {
+                Feature::Allowed { location: Location(x, y), displays } => rendered += &format!("only {} at ({x}, {y})\n", displays.iter().join("")),
+                Feature::Forbidden { location: Location(x, y), displays } => rendered += &format!("no {} at ({x}, {y})\n", displays.iter().join("")),
+                Feature::Checkpoint { display, location: Location(x, y) } => rendered += &format!("checkpoint {display} at ({x}, {y})\n"),
+                Feature::Length { display, length } => rendered += &format!("length of {display} from {} to {}\n", length.start(), length.end()),
+                _ => {}
+            }
+        }
+
+        rendered
+    }
+
+    /// Copy this board, then apply `solution` to the copy.
+    #[cfg(feature = "sat")]
+    fn with_solution(&self, solution: &Solution<Node<Sh>, Edge<Sh>>) -> Self {
+        Self {
+            graph: self.solved_graph(solution),
+            dims: self.dims,
+            affiliation_displays: self.affiliation_displays.clone(),
+            features: self.features.clone(),
+        }
+    }
+
+    /// Build a copy of this board's graph with every empty cell and every edge affiliated according to `solution`.
+    #[cfg(any(feature = "sat", feature = "search"))]
+    fn solved_graph(&self, solution: &Solution<Node<Sh>, Edge<Sh>>) -> UnGraphMap<Node<Sh>, Edge<Sh>> {
         let mut solved_graph: UnGraphMap<Node<Sh>, Edge<Sh>> = GraphMap::with_capacity(self.graph.node_count(), self.graph.edge_count());
+        // nodes are keyed by their cell, so remember where each one went
+        let mut new_nodes = HashMap::with_capacity(self.graph.node_count());
         for node in self.graph.nodes() {
-            let mut new_node = node.clone();
+            let mut new_node = node;
             if node.cell == Cell::Empty {
-                new_node.cell = Cell::Path { affiliation: *solution.get(&solver::HasAffiliation::from_node(node)).unwrap() }
+                // a cell which need not be filled may be left empty
+                match *solution.get(&HasAffiliation::from_node(node)).unwrap() {
+                    0 => {}
+                    affiliation => new_node.cell = Cell::Path { affiliation },
+                }
             }
             // existing Terminus and path cells can stay as is
 
             solved_graph.add_node(new_node);
+            new_nodes.insert(node, new_node);
         }
 
         for triple in self.graph.all_edges() {
             let (n1, n2, e) = triple;
 
             let mut new_e = *e;
-            new_e.affiliation = *solution.get(&solver::HasAffiliation::from_edge(triple)).unwrap();
+            new_e.affiliation = *solution.get(&HasAffiliation::from_edge(triple)).unwrap();
 
-            solved_graph.add_edge(
-                solved_graph.nodes().find(|n| n.location == n1.location).unwrap(),
-                solved_graph.nodes().find(|n| n.location == n2.location).unwrap(),
-                new_e);
+            solved_graph.add_edge(*new_nodes.get(&n1).unwrap(), *new_nodes.get(&n2).unwrap(), new_e);
         }
 
-        self.graph = solved_graph;
-        Ok(self)
+        solved_graph
     }
 }
 
 impl<Sh: FullShape> Display for Board<Sh> {
+    /// Prints the cells of the board, followed by a line for each restriction, checkpoint and length switched on, which no cell can show.
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", Sh::print(Sh::gph_to_array(self.dims, &self.graph).map(|cell| match cell.cell_type {
-            FrozenCellType::Terminus { affiliation } => self.affiliation_displays.get(affiliation.get()).unwrap().to_ascii_uppercase(),
-            FrozenCellType::Path { affiliation } => self.affiliation_displays.get(affiliation.get()).unwrap().to_ascii_lowercase(),
-            FrozenCellType::Bridge { .. } => '+',
-            FrozenCellType::Empty => '.',
-        })))
+        write!(f, "{}", self.render(&self.graph))
     }
 }
\ No newline at end of file
@@ -1,16 +1,20 @@
-use std::collections::HashSet;
+//! Builders, which describe a puzzle feature by feature and then produce a [`Board`].
+
+use std::collections::{HashMap, HashSet};
 use std::num::NonZero;
-use std::ops::IndexMut;
+use std::ops::{IndexMut, RangeInclusive};
 
 use itertools::Itertools;
 use ndarray::{Array2, AssignElem};
 use petgraph::graphmap::UnGraphMap;
 use unordered_pair::UnorderedPair;
 
-use crate::board::{Board, Edge, Node};
+use crate::board::{direct, Board, Edge, Features, Node};
 use crate::cell::Cell;
 use crate::location::{Dimension, Location};
-use crate::shape::{FullShape, SquareStep, Shape};
+use crate::shape::{FullShape, HexStep, Shape, SquareStep};
+use crate::affiliation::{affiliation_of, AffiliationID};
+use crate::graph::{Drawn, Guards, HasAffiliation, Terminus};
 
 /// Reasons a builder may become invalid while building.
 #[derive(Copy, Clone, Debug)]
@@ -19,6 +23,137 @@ pub enum BuilderInvalidReason {
     FeatureOutOfBounds,
     /// A warp was inserted in a direction which does not make sense; e.g. attempting to place warp on the right edge in the up direction.
     WarpBadDirection,
+    /// A path was drawn, or a location restricted or a checkpoint placed, for a display character which no pair of termini has.
+    UnknownDisplay,
+    /// A terminus sits at a location its own path may not pass through, as restricted by [`Builder::allow_only`] or [`Builder::forbid`].
+    ForbiddenTerminus,
+    /// A checkpoint can never be passed: it sits on a bridge, on a terminus or checkpoint of another path, or at a location its path may not pass through.
+    CheckpointConflict,
+    /// A length was given for a pair of termini which no path can have: the range is empty, or allows fewer than the two cells of the termini themselves.
+    BadLength,
+    /// A one-way edge or arrow can never be crossed: it runs across a wall or into a dropped location, or an arrow sits on a terminus or bridge.
+    BlockedDirection,
+}
+
+/// A feature of a board as placed by a [`Builder`], named when explaining why a board is unsolvable.
+///
+/// See [`SolverFailure::Conflict`](crate::SolverFailure::Conflict).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// A pair of termini, which display as `display`.
+    Termini {
+        /// The character identifying this pair.
+        display: char,
+        /// Where the termini are.
+        locations: (Location, Location),
+    },
+    /// A bridge, as placed by [`Builder::add_bridge`].
+    Bridge(Location),
+    /// A warp between two locations, as placed by [`SquareBoardBuilder::add_warp`].
+    Warp(UnorderedPair<Location>),
+    /// A location dropped from the board by [`Builder::drop_location`].
+    DroppedLocation(Location),
+    /// A wall between two locations, as placed by [`Builder::disconnect`].
+    Wall(UnorderedPair<Location>),
+    /// A segment of a path already drawn between two locations, as placed by [`Builder::draw`].
+    Drawn {
+        /// The character identifying the path.
+        display: char,
+        /// The locations the path passes between.
+        locations: UnorderedPair<Location>,
+    },
+    /// The rule that some path must pass through this location.
+    Filled(Location),
+    /// The rule that only the paths displayed as one of `displays` may pass through `location`, as placed by [`Builder::allow_only`].
+    Allowed {
+        /// Where the rule applies.
+        location: Location,
+        /// The characters identifying the paths which may pass through.
+        displays: Vec<char>,
+    },
+    /// The rule that none of the paths displayed as one of `displays` may pass through `location`, as placed by [`Builder::forbid`].
+    Forbidden {
+        /// Where the rule applies.
+        location: Location,
+        /// The characters identifying the paths which may not pass through.
+        displays: Vec<char>,
+    },
+    /// A checkpoint, which the path displayed as `display` must pass through, as placed by [`Builder::add_checkpoint`].
+    Checkpoint {
+        /// The character identifying the path.
+        display: char,
+        /// Where the checkpoint is.
+        location: Location,
+    },
+    /// The rule that the path displayed as `display` passes through a number of cells within `length`, as given to [`Builder::add_termini_with_length`].
+    Length {
+        /// The character identifying the path.
+        display: char,
+        /// How many cells the path may pass through, counting both termini.
+        length: RangeInclusive<usize>,
+    },
+    /// A one-way edge, which a path may only cross from `from` to `to`, as placed by [`Builder::add_one_way`].
+    OneWay {
+        /// Where a path crossing the edge comes from.
+        from: Location,
+        /// Where a path crossing the edge goes to.
+        to: Location,
+    },
+    /// An arrow, which a path must pass straight through, entering from `from` and leaving toward `to`, as placed by [`Builder::add_arrow`].
+    Arrow {
+        /// Where the arrow is.
+        location: Location,
+        /// The neighbor a path comes from.
+        from: Location,
+        /// The neighbor a path goes to.
+        to: Location,
+    },
+}
+
+/// Which ways a path may not run next to itself, as in Nikoli's Numberlink; see [`Builder::self_touch`].
+///
+/// By default both rules hold, as they do in Flow Free.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SelfTouch {
+    /// Two neighbouring cells on the same path must be joined, so that a path never runs alongside itself.
+    pub orthogonal: bool,
+    /// No square of four cells, such as a 2x2 block on a square board, lies wholly on one path.
+    ///
+    /// Implied by [`Self::orthogonal`], so only has an effect without it; every four-cell cycle on a hexagonal board has a diagonal, so none counts.
+    pub blocks: bool,
+}
+
+impl Default for SelfTouch {
+    fn default() -> Self {
+        Self {
+            orthogonal: true,
+            blocks: true,
+        }
+    }
+}
+
+impl Feature {
+    /// Every [`Location`] this feature involves.
+    pub fn locations(&self) -> Vec<Location> {
+        match self {
+            Self::Termini { locations, .. } => vec![locations.0, locations.1],
+            Self::Bridge(location) | Self::DroppedLocation(location) | Self::Filled(location) => vec![*location],
+            Self::Allowed { location, .. } | Self::Forbidden { location, .. } | Self::Checkpoint { location, .. } => vec![*location],
+            Self::Warp(locations) | Self::Wall(locations) | Self::Drawn { locations, .. } => vec![locations.0, locations.1],
+            Self::OneWay { from, to } => vec![*from, *to],
+            Self::Arrow { location, from, to } => vec![*from, *location, *to],
+            Self::Length { .. } => Vec::new(),
+        }
+    }
+
+    /// The edges this feature directs, each as the location a path must cross it from and the one it must cross it to, and whether a path must cross them.
+    pub(crate) fn directed(&self) -> (Vec<(Location, Location)>, bool) {
+        match self {
+            Self::OneWay { from, to } => (vec![(*from, *to)], false),
+            Self::Arrow { location, from, to } => (vec![(*from, *location), (*location, *to)], true),
+            _ => (Vec::new(), false),
+        }
+    }
 }
 
 /// Functionality all builders must implement, parametrised over the grid shape `Sh` of the resulting board.
@@ -32,6 +167,13 @@ pub trait Builder<Sh: FullShape>: Clone {
     /// May cause the builder to enter a [`FeatureOutOfBounds`](BuilderInvalidReason::FeatureOutOfBounds) invalid state if either location is out of bounds.
     /// If the builder is already in an invalid state, this function does nothing.
     fn add_termini(&mut self, display: char, locations: (Location, Location)) -> &mut Self;
+    /// Add termini as [`Self::add_termini`] does, whose path must pass through a number of cells within `length`, counting both termini.
+    /// A bridge counts once for each time the path passes through it.
+    ///
+    /// May cause the builder to enter invalid states as [`Self::add_termini`] does,
+    /// or a [`BadLength`](BuilderInvalidReason::BadLength) invalid state if no path could have such a length.
+    /// If the builder is already in an invalid state, this function does nothing.
+    fn add_termini_with_length(&mut self, display: char, locations: (Location, Location), length: RangeInclusive<usize>) -> &mut Self;
     /// Remove the most recently added pair of termini.
     ///
     /// If the builder is in an invalid state or no termini are present, this function does nothing.
@@ -48,7 +190,8 @@ pub trait Builder<Sh: FullShape>: Clone {
     /// Any feature here, such as a bridge, warp, or terminus, will be deleted regardless of where in the chain this method is called.
     /// Keep in mind this may cause the board to become unsolvable.
     ///
-    /// May cause the builder to enter a [`FeatureOutOfBounds`](BuilderInvalidReason::FeatureOutOfBounds) invalid state if `location` is out of bounds.
+    /// May cause the builder to enter a [`FeatureOutOfBounds`](BuilderInvalidReason::FeatureOutOfBounds) invalid state if `location` is out of bounds,
+    /// or a [`BlockedDirection`](BuilderInvalidReason::BlockedDirection) invalid state if a one-way edge or arrow leads into it.
     /// If the builder is already in an invalid state, this function does nothing.
     fn drop_location(&mut self, location: Location) -> &mut Self;
     /// Disconnect the two `locations`, i.e. place a wall between them.
@@ -56,14 +199,73 @@ pub trait Builder<Sh: FullShape>: Clone {
     /// A wall prevents paths from crossing it.
     /// If the two locations are not adjacent, this function does nothing and does not invalidate the builder.
     ///
-    /// May cause the builder to enter a [`FeatureOutOfBounds`](BuilderInvalidReason::FeatureOutOfBounds) invalid state if either location is out of bounds.
+    /// May cause the builder to enter a [`FeatureOutOfBounds`](BuilderInvalidReason::FeatureOutOfBounds) invalid state if either location is out of bounds,
+    /// or a [`BlockedDirection`](BuilderInvalidReason::BlockedDirection) invalid state if a one-way edge or arrow crosses the wall.
     /// If the builder is already in an invalid state, this function does nothing.
     fn disconnect(&mut self, locations: UnorderedPair<Location>) -> &mut Self;
+    /// Draw a segment of the path between the termini displayed as `display`, joining the two `locations`, as a player might partway through solving.
+    ///
+    /// Every solution must contain each drawn segment, and the cells it joins other than termini are drawn as part of the path.
+    /// If a segment is drawn again, the later one replaces it; segments drawn for a pair of termini are removed along with them.
+    /// If the two locations are neither adjacent nor joined by a warp, this function does nothing and does not invalidate the builder.
+    ///
+    /// May cause the builder to enter a [`FeatureOutOfBounds`](BuilderInvalidReason::FeatureOutOfBounds) invalid state if either location is out of bounds,
+    /// or an [`UnknownDisplay`](BuilderInvalidReason::UnknownDisplay) invalid state if no termini display as `display`.
+    /// If the builder is already in an invalid state, this function does nothing.
+    fn draw(&mut self, display: char, locations: UnorderedPair<Location>) -> &mut Self;
+    /// Allow only the paths displayed as one of `displays` to pass through `location`; if this is called again for the same location, the later call replaces it.
+    ///
+    /// May cause the builder to enter a [`FeatureOutOfBounds`](BuilderInvalidReason::FeatureOutOfBounds) invalid state if `location` is out of bounds,
+    /// an [`UnknownDisplay`](BuilderInvalidReason::UnknownDisplay) invalid state if no termini display as one of `displays`,
+    /// or a [`ForbiddenTerminus`](BuilderInvalidReason::ForbiddenTerminus) or [`CheckpointConflict`](BuilderInvalidReason::CheckpointConflict) invalid state if a terminus or checkpoint here is not allowed.
+    /// If the builder is already in an invalid state, this function does nothing.
+    fn allow_only(&mut self, location: Location, displays: Vec<char>) -> &mut Self;
+    /// Forbid the paths displayed as any of `displays` from passing through `location`; if this is called again for the same location, the later call replaces it.
+    ///
+    /// May cause the builder to enter invalid states as [`Self::allow_only`] does.
+    /// If the builder is already in an invalid state, this function does nothing.
+    fn forbid(&mut self, location: Location, displays: Vec<char>) -> &mut Self;
+    /// Add a checkpoint at `location`, which the path between the termini displayed as `display` must pass through.
+    /// Checkpoints placed for a pair of termini are removed along with them.
+    ///
+    /// May cause the builder to enter a [`FeatureOutOfBounds`](BuilderInvalidReason::FeatureOutOfBounds) invalid state if `location` is out of bounds,
+    /// an [`UnknownDisplay`](BuilderInvalidReason::UnknownDisplay) invalid state if no termini display as `display`,
+    /// or a [`CheckpointConflict`](BuilderInvalidReason::CheckpointConflict) invalid state if the path could never pass through it.
+    /// If the builder is already in an invalid state, this function does nothing.
+    fn add_checkpoint(&mut self, display: char, location: Location) -> &mut Self;
+    /// Make the edge from `location` to its neighbor in `direction` one-way, so that a path may only cross it travelling in `direction`.
+    /// Paths travel from either of their termini to the other, whichever their one-way edges allow.
+    ///
+    /// If the edge is already one-way, the later call replaces it.
+    /// A warp cannot be made one-way.
+    ///
+    /// May cause the builder to enter a [`FeatureOutOfBounds`](BuilderInvalidReason::FeatureOutOfBounds) invalid state if `location` or its neighbor is out of bounds,
+    /// or a [`BlockedDirection`](BuilderInvalidReason::BlockedDirection) invalid state if the edge crosses a wall or either location is dropped.
+    /// If the builder is already in an invalid state, this function does nothing.
+    fn add_one_way(&mut self, location: Location, direction: Sh) -> &mut Self;
+    /// Place an arrow at `location` pointing in `direction`, so that some path must pass straight through `location`, travelling in `direction`.
+    ///
+    /// This is like making the edges on either side of `location` along `direction` one-way with [`Self::add_one_way`], except that a path must cross them.
+    /// If an arrow is already at `location`, the later call replaces it.
+    ///
+    /// May cause the builder to enter a [`FeatureOutOfBounds`](BuilderInvalidReason::FeatureOutOfBounds) invalid state if `location` or either of its neighbors along `direction` is out of bounds,
+    /// or a [`BlockedDirection`](BuilderInvalidReason::BlockedDirection) invalid state if `location` holds a terminus or bridge, or a path could not pass straight through it for a wall or dropped location.
+    /// If the builder is already in an invalid state, this function does nothing.
+    fn add_arrow(&mut self, location: Location, direction: Sh) -> &mut Self;
     /// Shorthand for multiple calls to [`Self::disconnect`], with the same conditions.
     ///
     /// Disconnect cells neighboring `location`.
     /// Any appearance of a direction after the first in `directions` is ignored.
     fn disconnect_around(&mut self, location: Location, directions: Vec<Sh>) -> &mut Self;
+    /// Set whether cells may be left empty, as in classic Numberlink; by default, every cell must be covered by some path, as in Flow Free.
+    ///
+    /// Cells left empty by a solution stay empty and display as `.`, and any [`Feature::Filled`] starts switched off in a [`Session`](crate::Session).
+    /// This may be changed at any time, even while the builder is in an invalid state.
+    fn allow_empty(&mut self, allowed: bool) -> &mut Self;
+    /// Set which ways a path may not run next to itself; see [`SelfTouch`].
+    ///
+    /// This may be changed at any time, even while the builder is in an invalid state.
+    fn self_touch(&mut self, rules: SelfTouch) -> &mut Self;
     /// Check the validity of this builder, ensuring no [`BuilderInvalidReason`] condition has arisen.
     ///
     /// Returns `None` if the builder is valid, `Some(&Vec<BuilderInvalidReason>)` otherwise.
@@ -73,30 +275,39 @@ pub trait Builder<Sh: FullShape>: Clone {
     fn build(&self) -> Result<Board<Sh>, &Vec<BuilderInvalidReason>>;
 }
 
-/// A builder for boards with square-shaped cells, i.e. the rectangular boards found in Numberlink puzzles and in Flow Free and the Bridges and Warps expansions.
+/// State shared by the builders of every shape, along with the shape-agnostic parts of [`Builder`].
 #[derive(Clone)]
-pub struct SquareBoardBuilder {
+struct BuilderState<Sh: FullShape> {
     // width, height
     dims: (Dimension, Dimension),
-    cells: Array2<Cell<SquareStep>>,
+    cells: Array2<Cell<Sh>>,
     affiliation_displays: Vec<char>,
+    termini: Vec<(Location, Location)>,
+    // the number of cells each path must pass through, if limited
+    lengths: Vec<Option<RangeInclusive<usize>>>,
     invalid_reasons: Vec<BuilderInvalidReason>,
     // walls
     edge_blacklist: HashSet<UnorderedPair<Location>>,
     // holes
     location_blacklist: HashSet<Location>,
     bridges: HashSet<Location>,
-    // warps
-    edge_whitelist: HashSet<(UnorderedPair<Location>, SquareStep)>,
+    // drawn segments and their affiliations, in the order they were drawn
+    drawn: Vec<(UnorderedPair<Location>, AffiliationID)>,
+    // the displays of the paths allowed through, and forbidden from, each location
+    allowed: HashMap<Location, Vec<char>>,
+    forbidden: HashMap<Location, Vec<char>>,
+    // checkpoints and the affiliations which must pass through them, in the order they were placed
+    checkpoints: Vec<(Location, AffiliationID)>,
+    // one-way edges, as the locations paths cross them from and to
+    one_ways: HashMap<UnorderedPair<Location>, (Location, Location)>,
+    // arrows, as the locations paths pass through them from and to
+    arrows: HashMap<Location, (Location, Location)>,
+    // whether cells may be left empty
+    allow_empty: bool,
+    self_touch: SelfTouch,
 }
 
-impl Default for SquareBoardBuilder {
-    fn default() -> Self {
-        Self::with_dims((NonZero::new(5).unwrap(), NonZero::new(5).unwrap()))
-    }
-}
-
-impl Builder<SquareStep> for SquareBoardBuilder {
+impl<Sh: FullShape> BuilderState<Sh> {
     fn with_dims(dims: (Dimension, Dimension)) -> Self {
         Self {
             dims,
@@ -106,106 +317,272 @@ impl Builder<SquareStep> for SquareBoardBuilder {
             edge_blacklist: Default::default(),
             location_blacklist: Default::default(),
             bridges: Default::default(),
-            edge_whitelist: Default::default(),
+            drawn: Default::default(),
+            allowed: Default::default(),
+            forbidden: Default::default(),
+            checkpoints: Default::default(),
+            one_ways: Default::default(),
+            arrows: Default::default(),
+            allow_empty: false,
+            self_touch: Default::default(),
             affiliation_displays: Default::default(),
+            termini: Default::default(),
+            lengths: Default::default(),
         }
     }
 
-    fn add_termini(&mut self, display: char, locations: (Location, Location)) -> &mut Self {
+    #[inline]
+    fn in_bounds(&self, location: Location) -> bool {
+        location.0 < self.dims.0.get() && location.1 < self.dims.1.get()
+    }
+
+    fn add_termini(&mut self, display: char, locations: (Location, Location), length: Option<RangeInclusive<usize>>) {
         if !self.invalid_reasons.is_empty() {
-            return self;
+            return;
+        }
+
+        if length.as_ref().is_some_and(|length| length.is_empty() || *length.end() < 2) {
+            self.invalid_reasons.push(BuilderInvalidReason::BadLength);
+            return;
         }
 
         for location in [locations.0, locations.1] {
-            if location.0 >= self.dims.0.get() || location.1 >= self.dims.1.get() {
+            if !self.in_bounds(location) {
                 self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
-                return self;
+                return;
             }
         }
 
         // non-null affiliation IDs start at 1
         let aff = self.affiliation_displays.len() + 1;
         self.affiliation_displays.push(display);
+        self.termini.push(locations);
+        self.lengths.push(length);
         for location in [locations.0, locations.1] {
             self.cells.index_mut(location.as_index()).assign_elem(Cell::Terminus { affiliation: aff })
         }
-
-        self
+        self.check_restrictions();
     }
 
-    fn pop_termini(&mut self) -> &mut Self {
+    fn pop_termini(&mut self) {
         if !self.invalid_reasons.is_empty() {
-            return self;
+            return;
         }
 
         let aff_to_remove = self.affiliation_displays.len();
         let display = self.affiliation_displays.pop();
+        self.termini.pop();
+        self.lengths.pop();
+        self.drawn.retain(|(_, aff)| *aff != aff_to_remove);
+        self.checkpoints.retain(|(_, aff)| *aff != aff_to_remove);
+        // restrictions name paths by display, so only forget it if no earlier pair of termini shares it
+        if let Some(display) = display.filter(|display| !self.affiliation_displays.contains(display)) {
+            for restrictions in [&mut self.allowed, &mut self.forbidden] {
+                restrictions.values_mut().for_each(|displays| displays.retain(|other| *other != display));
+                restrictions.retain(|_, displays| !displays.is_empty());
+            }
+        }
         if display.is_some() {
             self.cells.map_inplace(|cell| {
-                match cell {
-                    Cell::Terminus { affiliation } => if *affiliation == aff_to_remove {
+                if let Cell::Terminus { affiliation } = cell {
+                    if *affiliation == aff_to_remove {
                         cell.assign_elem(Cell::Empty);
-                    },
-                    _ => {}
+                    }
                 }
             })
         }
-
-        self
     }
 
-    fn add_bridge(&mut self, location: Location) -> &mut Self {
+    fn add_bridge(&mut self, location: Location) {
         if !self.invalid_reasons.is_empty() {
-            return self;
+            return;
         }
 
+        // every direction must lead somewhere, so bridges may not sit on the border
         // todo: check this better; bridges right next to warps are *technically* possible
-        if !(1..(self.dims.0.get() - 1)).contains(&location.0) || !(1..(self.dims.1.get() - 1)).contains(&location.1) {
+        if !Sh::VARIANTS.iter().all(|dir| self.in_bounds(dir.attempt_from(location))) {
             self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
-            return self;
+            return;
         }
 
         self.bridges.insert(location);
-        self
+        self.check_restrictions();
     }
 
-    fn drop_location(&mut self, location: Location) -> &mut Self {
+    fn drop_location(&mut self, location: Location) {
         if !self.invalid_reasons.is_empty() {
-            return self;
+            return;
         }
 
-        if location.0 >= self.dims.0.get() || location.1 >= self.dims.1.get() {
+        if !self.in_bounds(location) {
             self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
-            return self;
+            return;
         }
 
         self.location_blacklist.insert(location);
-        self
+        self.check_restrictions();
     }
 
-    fn disconnect(&mut self, locations: UnorderedPair<Location>) -> &mut Self {
+    fn disconnect(&mut self, locations: UnorderedPair<Location>) {
         for location in [locations.0, locations.1] {
-            if location.0 >= self.dims.0.get() || location.1 >= self.dims.1.get() {
+            if !self.in_bounds(location) {
                 self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
-                return self;
+                return;
             }
         }
 
-        if !SquareStep::direction_to(locations.0, locations.1).is_some() {
-            return self;
+        if Sh::direction_to(locations.0, locations.1).is_none() {
+            return;
         }
 
         self.edge_blacklist.insert(locations);
+        self.check_restrictions();
+    }
 
-        self
+    /// Draw a segment as in [`Builder::draw`]; `warped` says whether the locations are joined by a warp.
+    fn draw(&mut self, display: char, locations: UnorderedPair<Location>, warped: bool) {
+        if !self.invalid_reasons.is_empty() {
+            return;
+        }
+
+        for location in [locations.0, locations.1] {
+            if !self.in_bounds(location) {
+                self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
+                return;
+            }
+        }
+
+        let Some(aff) = affiliation_of(&self.affiliation_displays, display) else {
+            self.invalid_reasons.push(BuilderInvalidReason::UnknownDisplay);
+            return;
+        };
+
+        if Sh::direction_to(locations.0, locations.1).is_none() && !warped {
+            return;
+        }
+
+        self.drawn.retain(|(other, _)| *other != locations);
+        self.drawn.push((locations, aff));
     }
 
-    fn disconnect_around(&mut self, location: Location, directions: Vec<SquareStep>) -> &mut Self {
+    /// Restrict the paths through `location` as in [`Builder::allow_only`] if `allowed`, or else as in [`Builder::forbid`].
+    fn restrict(&mut self, location: Location, displays: Vec<char>, allowed: bool) {
+        if !self.invalid_reasons.is_empty() {
+            return;
+        }
+
+        if !self.in_bounds(location) {
+            self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
+            return;
+        }
+
+        if !displays.iter().all(|display| self.affiliation_displays.contains(display)) {
+            self.invalid_reasons.push(BuilderInvalidReason::UnknownDisplay);
+            return;
+        }
+
+        match allowed {
+            true => self.allowed.insert(location, displays),
+            false => self.forbidden.insert(location, displays),
+        };
+        self.check_restrictions();
+    }
+
+    fn add_checkpoint(&mut self, display: char, location: Location) {
+        if !self.invalid_reasons.is_empty() {
+            return;
+        }
+
+        if !self.in_bounds(location) {
+            self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
+            return;
+        }
+
+        let Some(aff) = affiliation_of(&self.affiliation_displays, display) else {
+            self.invalid_reasons.push(BuilderInvalidReason::UnknownDisplay);
+            return;
+        };
+
+        if !self.checkpoints.contains(&(location, aff)) {
+            self.checkpoints.push((location, aff));
+        }
+        self.check_restrictions();
+    }
+
+    fn add_one_way(&mut self, location: Location, direction: Sh) {
+        if !self.invalid_reasons.is_empty() {
+            return;
+        }
+
+        let to = direction.attempt_from(location);
+        if !self.in_bounds(location) || !self.in_bounds(to) {
+            self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
+            return;
+        }
+
+        self.one_ways.insert(UnorderedPair(location, to), (location, to));
+        self.check_restrictions();
+    }
+
+    fn add_arrow(&mut self, location: Location, direction: Sh) {
+        if !self.invalid_reasons.is_empty() {
+            return;
+        }
+
+        let (from, to) = (direction.invert().attempt_from(location), direction.attempt_from(location));
+        if ![from, location, to].into_iter().all(|location| self.in_bounds(location)) {
+            self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
+            return;
+        }
+
+        self.arrows.insert(location, (from, to));
+        self.check_restrictions();
+    }
+
+    /// Whether the path displayed as `display` may pass through `location`.
+    fn permitted(&self, location: Location, display: char) -> bool {
+        self.allowed.get(&location).is_none_or(|displays| displays.contains(&display))
+            && !self.forbidden.get(&location).is_some_and(|displays| displays.contains(&display))
+    }
+
+    /// Invalidate this builder if some terminus sits where its path may not pass, or some checkpoint, one-way edge or arrow can never be passed.
+    fn check_restrictions(&mut self) {
+        let forbidden_terminus = self.cells.indexed_iter().any(|(index, cell)| match cell {
+            Cell::Terminus { affiliation } => !self.permitted(Location::from(index), self.affiliation_displays[affiliation - 1]),
+            _ => false,
+        });
+        if forbidden_terminus {
+            self.invalid_reasons.push(BuilderInvalidReason::ForbiddenTerminus);
+            return;
+        }
+
+        let checkpoint_conflict = self.checkpoints.iter().any(|(location, aff)| {
+            let other_terminus = matches!(self.cells.get(location.as_index()), Some(Cell::Terminus { affiliation }) if affiliation != aff);
+            let other_checkpoint = self.checkpoints.iter().any(|(other, other_aff)| other == location && other_aff != aff);
+            other_terminus || other_checkpoint || self.bridges.contains(location) || !self.permitted(*location, self.affiliation_displays[aff - 1])
+        });
+        if checkpoint_conflict {
+            self.invalid_reasons.push(BuilderInvalidReason::CheckpointConflict);
+            return;
+        }
+
+        let open = |from: Location, to: Location| {
+            !self.edge_blacklist.contains(&UnorderedPair(from, to)) && !self.location_blacklist.contains(&from) && !self.location_blacklist.contains(&to)
+        };
+        let blocked_one_way = self.one_ways.values().any(|(from, to)| !open(*from, *to));
+        let blocked_arrow = self.arrows.iter().any(|(location, (from, to))| {
+            let passable = matches!(self.cells.get(location.as_index()), Some(Cell::Empty)) && !self.bridges.contains(location);
+            !passable || !open(*from, *location) || !open(*location, *to)
+        });
+        if blocked_one_way || blocked_arrow {
+            self.invalid_reasons.push(BuilderInvalidReason::BlockedDirection);
+        }
+    }
+
+    fn disconnect_around(&mut self, location: Location, directions: Vec<Sh>) {
         for direction in directions {
             self.disconnect(UnorderedPair::from((location, direction.attempt_from(location))));
         }
-
-        self
     }
 
     fn is_valid(&self) -> Option<&Vec<BuilderInvalidReason>> {
@@ -216,49 +593,118 @@ impl Builder<SquareStep> for SquareBoardBuilder {
         }
     }
 
-    fn build(&self) -> Result<Board<SquareStep>, &Vec<BuilderInvalidReason>> {
+    /// Build the graph of every location connected to its neighbors in each direction, plus `extra_edges` (e.g. warps), then apply bridges, holes, and walls.
+    fn build(&self, extra_edges: impl IntoIterator<Item=(UnorderedPair<Location>, Sh)>) -> Result<Board<Sh>, &Vec<BuilderInvalidReason>> {
         if !self.invalid_reasons.is_empty() {
             return Err(&self.invalid_reasons);
         }
 
+        let features = self.features(extra_edges);
+        // with every feature in place, keep only what remains
+        let graph = features.graph(None);
+
+        let mut affiliation_displays = Vec::with_capacity(self.affiliation_displays.len() + 1);
+        // affiliation 0 is unaffiliated and will display as empty
+        affiliation_displays.push('.');
+        affiliation_displays.extend(self.affiliation_displays.clone());
+
+        Ok(Board {
+            graph,
+            dims: self.dims,
+            affiliation_displays,
+            features,
+        })
+    }
+
+    /// List every feature of this builder, then build a graph containing everything any of them could add, along with [`Guards`] under which each can be switched on or off.
+    ///
+    /// Every selector is on by default, except that [`Feature::Filled`] locations are off if cells may be left empty.
+    fn features(&self, extra_edges: impl IntoIterator<Item=(UnorderedPair<Location>, Sh)>) -> Features<Sh> {
         let mut graph = UnGraphMap::with_capacity(
             // naively allocate for a complete grid of this size, which usually isn't too far off
             self.cells.len(),
-            // "horizontal" edges
-            (self.dims.0.get() - 1) * self.dims.1.get()
-                // "vertical" edges
-                + (self.dims.1.get() - 1) * self.dims.0.get(),
+            self.cells.len() * Sh::FORWARD_VARIANTS.len(),
         );
+        let mut guards: Guards<Node<Sh>, Edge<Sh>> = Guards::default();
+        let mut features = Vec::new();
+
+        // cells joined by a drawn segment are drawn as part of its path, except for termini; later segments win
+        let mut cells = self.cells.clone();
+        for (locations, aff) in &self.drawn {
+            for location in [locations.0, locations.1] {
+                let cell = cells.index_mut(location.as_index());
+                if !matches!(cell, Cell::Terminus { .. }) {
+                    cell.assign_elem(Cell::Path { affiliation: *aff });
+                }
+            }
+        }
+        let drawn_aff = |locations: UnorderedPair<Location>| self.drawn.iter()
+            .find(|(other, _)| *other == locations)
+            .map_or(0, |(_, aff)| *aff);
 
-        let nodes = Array2::from_shape_fn(self.cells.raw_dim(), |ind| Node {
+        let nodes = Array2::from_shape_fn(cells.raw_dim(), |ind| Node {
             location: Location::from(ind),
-            cell: *self.cells.get(ind).unwrap(),
+            cell: *cells.get(ind).unwrap(),
         });
 
         for x in 0..self.dims.0.get() {
             for y in 0..self.dims.1.get() {
                 let location = Location(x, y);
-                // add edges down and to the right, if possible
-                let location_below = SquareStep::Down.attempt_from(location);
-                let location_right = SquareStep::Right.attempt_from(location);
-
                 let node = nodes.get(location.as_index()).unwrap();
-                let node_below = nodes.get(location_below.as_index());
-                let node_right = nodes.get(location_right.as_index());
+                // a location need not have any neighbors, but it is still on the board
+                graph.add_node(*node);
+
+                // add edges in every forward direction, if possible
+                for direction in Sh::FORWARD_VARIANTS {
+                    let other_location = direction.attempt_from(location);
+                    if !self.in_bounds(other_location) {
+                        continue;
+                    }
 
-                node_below.and_then(|other_node| graph.add_edge(*node, *other_node, Edge { affiliation: 0, direction: SquareStep::Down }));
-                node_right.and_then(|other_node| graph.add_edge(*node, *other_node, Edge { affiliation: 0, direction: SquareStep::Right }));
+                    let other_node = nodes.get(other_location.as_index()).unwrap();
+                    let affiliation = drawn_aff(UnorderedPair(location, other_location));
+                    graph.add_edge(*node, *other_node, Edge { affiliation, direction: *direction });
+                }
             }
         }
 
-        for (UnorderedPair(l1, l2), direction) in self.edge_whitelist.iter() {
-            graph.add_edge(*nodes.get(l1.as_index()).unwrap(), *nodes.get(l2.as_index()).unwrap(), Edge { affiliation: 0, direction: *direction });
+        for (display, locations) in self.affiliation_displays.iter().zip(self.termini.iter()) {
+            let selector = guards.selectors.len();
+            guards.selectors.push(true);
+            features.push(Feature::Termini { display: *display, locations: *locations });
+
+            // the later of two overlapping pairs of termini wins, so look at what is actually there
+            for location in [locations.0, locations.1] {
+                let node = nodes.get(location.as_index()).unwrap();
+                if node.is_terminus().is_some_and(|aff| aff.get() == selector + 1) {
+                    guards.termini.insert(*node, vec![(selector, true)]);
+                }
+            }
         }
 
-        // we replace nodes at a bridge location with multiple nodes, all sharing a location, but each has neighbors only in two opposing directions
-        for bridge_loc in &self.bridges {
-            // assume there isn't already a bridge here (bridges is hashset so that'll be true)
-            let existing_node_here = graph.nodes().find(|n| n.location == *bridge_loc).unwrap();
+        // the builder keeps warps and other features in sets, so sort them to be sure the result is the same every time
+        for (locations, direction) in extra_edges.into_iter().sorted_by_key(|(locations, direction)| (locations.into_ordered_tuple(), *direction)) {
+            let UnorderedPair(l1, l2) = locations;
+            let (n1, n2) = (*nodes.get(l1.as_index()).unwrap(), *nodes.get(l2.as_index()).unwrap());
+            let e = Edge { affiliation: drawn_aff(locations), direction };
+            if graph.add_edge(n1, n2, e).is_some() {
+                // the locations were already neighbors
+                continue;
+            }
+
+            guards.edges.insert(HasAffiliation::from_edge((n1, n2, &e)), vec![(guards.selectors.len(), true)]);
+            guards.selectors.push(true);
+            features.push(Feature::Warp(locations));
+        }
+
+        // while a bridge is in place, the node at its location is replaced with multiple nodes, all sharing a location, but each has neighbors only in two opposing directions
+        for bridge_loc in self.bridges.iter().sorted() {
+            let selector = guards.selectors.len();
+            guards.selectors.push(true);
+            features.push(Feature::Bridge(*bridge_loc));
+
+            let existing_node_here = *nodes.get(bridge_loc.as_index()).unwrap();
+            guards.nodes.entry(existing_node_here).or_default().push((selector, false));
 
             // deref and collect to avoid mutating ref inside iterator borrowing ref
             let old_edges = graph.edges(existing_node_here)
@@ -276,46 +722,272 @@ impl Builder<SquareStep> for SquareBoardBuilder {
                         direction: e.direction.ensure_forward(),
                     },
                 };
+                if !graph.contains_node(bridge_node_this_direction) {
+                    graph.add_node(bridge_node_this_direction);
+                    guards.nodes.insert(bridge_node_this_direction, vec![(selector, true)]);
+                }
 
-                graph.add_edge(other, bridge_node_this_direction, Edge {
-                    affiliation: 0,
+                // a drawn edge stays drawn
+                let new_e = Edge {
+                    affiliation: e.affiliation,
                     direction: e.direction,
-                });
+                };
+                graph.add_edge(other, bridge_node_this_direction, new_e);
+
+                // an edge which is a warp remains one
+                if let Some(condition) = guards.edges.get(&HasAffiliation::from_edge((n1, n2, &e))).cloned() {
+                    guards.edges.insert(HasAffiliation::from_edge((other, bridge_node_this_direction, &new_e)), condition);
+                }
             }
+        }
+
+        let nodes_at = graph.nodes().into_group_map_by(|n| n.location);
+
+        for location in self.location_blacklist.iter().sorted() {
+            let selector = guards.selectors.len();
+            guards.selectors.push(true);
+            features.push(Feature::DroppedLocation(*location));
 
-            // cut the old one out
-            graph.remove_node(existing_node_here);
+            for node in &nodes_at[location] {
+                guards.nodes.entry(*node).or_default().push((selector, false));
+            }
         }
 
-        for location in self.location_blacklist.iter() {
-            let to_rm = graph.nodes().filter(|n| n.location == *location).collect_vec();
-            to_rm.iter().for_each(|n| { graph.remove_node(*n); });
+        for locations in self.edge_blacklist.iter().sorted_by_key(|locations| locations.into_ordered_tuple()) {
+            let selector = guards.selectors.len();
+            guards.selectors.push(true);
+            features.push(Feature::Wall(*locations));
+
+            let UnorderedPair(l1, l2) = locations;
+            for (n1, n2) in nodes_at[l1].iter().cartesian_product(nodes_at[l2].iter()) {
+                if let Some(e) = graph.edge_weight(*n1, *n2) {
+                    guards.edges.entry(HasAffiliation::from_edge((*n1, *n2, e))).or_default().push((selector, false));
+                }
+            }
         }
 
-        for UnorderedPair(l1, l2) in self.edge_blacklist.iter() {
-            for (n1, n2) in graph.nodes().filter(|n| n.location == (*l1)).collect_vec().into_iter()
-                .cartesian_product(graph.nodes().filter(|n| n.location == (*l2)).collect_vec().into_iter()) {
-                graph.remove_edge(n1, n2);
+        // a drawn segment holds only while its cells are present, and a drawn cell only while every segment drawn through it holds
+        for (locations, aff) in &self.drawn {
+            let selector = guards.selectors.len();
+            guards.selectors.push(true);
+            features.push(Feature::Drawn { display: self.affiliation_displays[aff - 1], locations: *locations });
+
+            let UnorderedPair(l1, l2) = locations;
+            for (n1, n2) in nodes_at[l1].iter().cartesian_product(nodes_at[l2].iter()) {
+                if let Some(e) = graph.edge_weight(*n1, *n2) {
+                    let condition = [guards.nodes.get(n1), guards.nodes.get(n2)].into_iter()
+                        .flatten()
+                        .flatten()
+                        .copied()
+                        .chain([(selector, true)])
+                        .collect_vec();
+                    guards.drawn.insert(HasAffiliation::from_edge((*n1, *n2, e)), condition);
+                }
+            }
+
+            for node in nodes_at[l1].iter().chain(&nodes_at[l2]).filter(|node| node.drawn().is_some()) {
+                guards.drawn.entry(HasAffiliation::from_node(*node))
+                    .or_insert_with(|| guards.nodes.get(node).cloned().unwrap_or_default())
+                    .push((selector, true));
             }
         }
 
-        let mut affiliation_displays = Vec::with_capacity(self.affiliation_displays.len() + 1);
-        // affiliation 0 is unaffiliated and will display as empty
-        affiliation_displays.push('.');
-        affiliation_displays.extend(self.affiliation_displays.clone());
+        // a restriction forbids each vertex at its location from taking the affiliations of the paths it keeps out
+        let restrictions = self.allowed.iter().map(|(location, displays)| (location, displays, true))
+            .chain(self.forbidden.iter().map(|(location, displays)| (location, displays, false)))
+            .sorted_by_key(|(location, _, allowed)| (**location, !*allowed));
+        for (location, displays, allowed) in restrictions {
+            let selector = guards.selectors.len();
+            guards.selectors.push(true);
+            features.push(match allowed {
+                true => Feature::Allowed { location: *location, displays: displays.clone() },
+                false => Feature::Forbidden { location: *location, displays: displays.clone() },
+            });
 
-        Ok(Board {
-            graph,
-            dims: self.dims,
-            affiliation_displays,
-        })
+            let kept_out = self.affiliation_displays.iter()
+                .positions(|display| displays.contains(display) != allowed)
+                .map(|index| (index + 1, vec![(selector, true)]))
+                .collect_vec();
+            for node in &nodes_at[location] {
+                guards.forbidden.entry(*node).or_default().extend(kept_out.iter().cloned());
+            }
+        }
+
+        for (location, aff) in &self.checkpoints {
+            let selector = guards.selectors.len();
+            guards.selectors.push(true);
+            features.push(Feature::Checkpoint { display: self.affiliation_displays[aff - 1], location: *location });
+
+            for node in &nodes_at[location] {
+                guards.required.entry(*node).or_default().push((*aff, vec![(selector, true)]));
+            }
+        }
+
+        // a length holds only while its termini do, whose selectors come first
+        for (index, length) in self.lengths.iter().enumerate() {
+            if let Some(length) = length {
+                let selector = guards.selectors.len();
+                guards.selectors.push(true);
+                features.push(Feature::Length { display: self.affiliation_displays[index], length: length.clone() });
+                guards.lengths.entry(index + 1).or_default().push((length.clone(), vec![(selector, true), (index, true)]));
+            }
+        }
+
+        // one-way edges and arrows direct the edges between their locations
+        let directing = self.one_ways.values()
+            .sorted()
+            .map(|(from, to)| Feature::OneWay { from: *from, to: *to })
+            .chain(self.arrows.iter().sorted().map(|(location, (from, to))| Feature::Arrow { location: *location, from: *from, to: *to }))
+            .collect_vec();
+        for feature in directing {
+            let selector = guards.selectors.len();
+            guards.selectors.push(true);
+            direct(&graph, &mut guards, &feature, vec![(selector, true)]);
+            features.push(feature);
+        }
+
+        // every location must be filled, which is only a feature in the sense that an explanation may need to point out a location which cannot be
+        // where cells may be left empty, these are switched off instead
+        for x in 0..self.dims.0.get() {
+            for y in 0..self.dims.1.get() {
+                let location = Location(x, y);
+                let selector = guards.selectors.len();
+                guards.selectors.push(!self.allow_empty);
+                features.push(Feature::Filled(location));
+
+                for node in &nodes_at[&location] {
+                    guards.filled.insert(*node, vec![(selector, true)]);
+                }
+            }
+        }
+
+        Features {
+            list: features,
+            superset: graph,
+            guards,
+            self_touch: self.self_touch,
+        }
+    }
+}
+
+/// The methods of [`Builder`] which every builder hands straight to its [`BuilderState`], for use inside `impl Builder<$step>`.
+macro_rules! delegate_to_state {
+    ($step:ty) => {
+        fn add_termini(&mut self, display: char, locations: (Location, Location)) -> &mut Self {
+            self.state.add_termini(display, locations, None);
+            self
+        }
+
+        fn add_termini_with_length(&mut self, display: char, locations: (Location, Location), length: RangeInclusive<usize>) -> &mut Self {
+            self.state.add_termini(display, locations, Some(length));
+            self
+        }
+
+        fn pop_termini(&mut self) -> &mut Self {
+            self.state.pop_termini();
+            self
+        }
+
+        fn add_bridge(&mut self, location: Location) -> &mut Self {
+            self.state.add_bridge(location);
+            self
+        }
+
+        fn drop_location(&mut self, location: Location) -> &mut Self {
+            self.state.drop_location(location);
+            self
+        }
+
+        fn disconnect(&mut self, locations: UnorderedPair<Location>) -> &mut Self {
+            self.state.disconnect(locations);
+            self
+        }
+
+        fn allow_only(&mut self, location: Location, displays: Vec<char>) -> &mut Self {
+            self.state.restrict(location, displays, true);
+            self
+        }
+
+        fn forbid(&mut self, location: Location, displays: Vec<char>) -> &mut Self {
+            self.state.restrict(location, displays, false);
+            self
+        }
+
+        fn add_checkpoint(&mut self, display: char, location: Location) -> &mut Self {
+            self.state.add_checkpoint(display, location);
+            self
+        }
+
+        fn add_one_way(&mut self, location: Location, direction: $step) -> &mut Self {
+            self.state.add_one_way(location, direction);
+            self
+        }
+
+        fn add_arrow(&mut self, location: Location, direction: $step) -> &mut Self {
+            self.state.add_arrow(location, direction);
+            self
+        }
+
+        fn disconnect_around(&mut self, location: Location, directions: Vec<$step>) -> &mut Self {
+            self.state.disconnect_around(location, directions);
+            self
+        }
+
+        fn allow_empty(&mut self, allowed: bool) -> &mut Self {
+            self.state.allow_empty = allowed;
+            self
+        }
+
+        fn self_touch(&mut self, rules: SelfTouch) -> &mut Self {
+            self.state.self_touch = rules;
+            self
+        }
+
+        fn is_valid(&self) -> Option<&Vec<BuilderInvalidReason>> {
+            self.state.is_valid()
+        }
+    };
+}
+
+/// A builder for boards with square-shaped cells, i.e. the rectangular boards found in Numberlink puzzles and in Flow Free and the Bridges and Warps expansions.
+#[derive(Clone)]
+pub struct SquareBoardBuilder {
+    state: BuilderState<SquareStep>,
+    // warps
+    edge_whitelist: HashSet<(UnorderedPair<Location>, SquareStep)>,
+}
+
+impl Default for SquareBoardBuilder {
+    fn default() -> Self {
+        Self::with_dims((NonZero::new(5).unwrap(), NonZero::new(5).unwrap()))
+    }
+}
+
+impl Builder<SquareStep> for SquareBoardBuilder {
+    fn with_dims(dims: (Dimension, Dimension)) -> Self {
+        Self {
+            state: BuilderState::with_dims(dims),
+            edge_whitelist: Default::default(),
+        }
+    }
+
+    delegate_to_state!(SquareStep);
+
+    fn draw(&mut self, display: char, locations: UnorderedPair<Location>) -> &mut Self {
+        let warped = self.edge_whitelist.iter().any(|(other, _)| *other == locations);
+        self.state.draw(display, locations, warped);
+        self
+    }
+
+    fn build(&self) -> Result<Board<SquareStep>, &Vec<BuilderInvalidReason>> {
+        self.state.build(self.edge_whitelist.iter().copied())
     }
 }
 
 impl SquareBoardBuilder {
     #[inline]
     fn max_loc(&self) -> Location {
-        Location(self.dims.0.get() - 1, self.dims.1.get() - 1)
+        Location(self.state.dims.0.get() - 1, self.state.dims.1.get() - 1)
     }
 
     /// Add a warp at the specified `location` pointing in `direction`.
@@ -328,18 +1000,18 @@ impl SquareBoardBuilder {
     /// May cause the builder to enter a [`WarpBadDirection`](BuilderInvalidReason::WarpBadDirection) invalid state if `location` is at a corner and `direction` is missing or is impossible for a warp at this location.
     /// If the builder is already in an invalid state, this function does nothing.
     pub fn add_warp(&mut self, location: Location, direction: Option<SquareStep>) -> &mut Self {
-        if !self.invalid_reasons.is_empty() {
+        if !self.state.invalid_reasons.is_empty() {
             return self;
         }
 
-        if location > self.max_loc() {
-            self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
+        if !self.state.in_bounds(location) {
+            self.state.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
             return self;
         }
 
         // not on any edge
         if location.0 != 0 && location.1 != 0 && location.0 != self.max_loc().0 && location.1 != self.max_loc().1 {
-            self.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
+            self.state.invalid_reasons.push(BuilderInvalidReason::FeatureOutOfBounds);
             return self;
         }
 
@@ -352,7 +1024,7 @@ impl SquareBoardBuilder {
 
         let edge = if is_corner {
             if direction.is_none() {
-                self.invalid_reasons.push(BuilderInvalidReason::WarpBadDirection);
+                self.state.invalid_reasons.push(BuilderInvalidReason::WarpBadDirection);
                 return self;
             }
 
@@ -361,7 +1033,7 @@ impl SquareBoardBuilder {
             match location {
                 Location(0, _) => SquareStep::Left,
                 Location(_, 0) => SquareStep::Up,
-                Location(x, y) => {
+                Location(x, _) => {
                     if x == self.max_loc().0 {
                         SquareStep::Right
                     } else {
@@ -382,7 +1054,7 @@ impl SquareBoardBuilder {
         if partner == location {
             // then the given direction was bad
             // e.g. top-left corner with down direction specified => "partner" is the first cell in the column => this condition succeeds
-            self.invalid_reasons.push(BuilderInvalidReason::WarpBadDirection);
+            self.state.invalid_reasons.push(BuilderInvalidReason::WarpBadDirection);
             return self;
         }
 
@@ -391,4 +1063,37 @@ impl SquareBoardBuilder {
 
         self
     }
-}
\ No newline at end of file
+}
+
+/// A builder for boards with hexagonal cells, as found in the Flow Free Hexes expansion.
+///
+/// See [`HexStep`] for the layout of locations; `dims` counts columns and rows of the cell array, so the hexagons directly above and below one another are two rows apart.
+#[derive(Clone)]
+pub struct HexBoardBuilder {
+    state: BuilderState<HexStep>,
+}
+
+impl Default for HexBoardBuilder {
+    fn default() -> Self {
+        Self::with_dims((NonZero::new(5).unwrap(), NonZero::new(5).unwrap()))
+    }
+}
+
+impl Builder<HexStep> for HexBoardBuilder {
+    fn with_dims(dims: (Dimension, Dimension)) -> Self {
+        Self {
+            state: BuilderState::with_dims(dims),
+        }
+    }
+
+    delegate_to_state!(HexStep);
+
+    fn draw(&mut self, display: char, locations: UnorderedPair<Location>) -> &mut Self {
+        self.state.draw(display, locations, false);
+        self
+    }
+
+    fn build(&self) -> Result<Board<HexStep>, &Vec<BuilderInvalidReason>> {
+        self.state.build([])
+    }
+}
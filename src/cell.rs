@@ -17,7 +17,10 @@ pub(crate) enum Cell<Sh: FullShape> {
 pub(crate) enum FrozenCellType<Sh: FullShape> {
     Terminus { affiliation: NonZero<AffiliationID> },
     Path { affiliation: NonZero<AffiliationID> },
-    Bridge { affiliations: HashMap<Sh, Option<NonZero<AffiliationID>>> },
+    Bridge {
+        #[allow(dead_code)]
+        affiliations: HashMap<Sh, Option<NonZero<AffiliationID>>>,
+    },
     #[default]
     Empty,
 }
@@ -25,6 +28,8 @@ pub(crate) enum FrozenCellType<Sh: FullShape> {
 /// Cells, frozen for output or printing.
 #[derive(Clone)]
 pub(crate) struct FrozenCell<Sh: FullShape> {
+    // exits and bridge affiliations are not yet rendered by any shape
+    #[allow(dead_code)]
     pub(crate) exits: HashSet<Sh>,
     pub(crate) cell_type: FrozenCellType<Sh>,
 }
@@ -0,0 +1,184 @@
+//! The graphs which boards are solved on, and what solving them yields, however they are solved.
+
+use std::collections::HashMap;
+use std::num::NonZero;
+use std::ops::RangeInclusive;
+
+#[cfg(any(feature = "sat", feature = "search"))]
+use itertools::Itertools;
+use petgraph::graphmap::NodeTrait;
+#[cfg(any(feature = "sat", feature = "search"))]
+use petgraph::graphmap::UnGraphMap;
+use unordered_pair::UnorderedPair;
+
+use crate::affiliation::AffiliationID;
+use crate::builder::Feature;
+
+/// Constraint on node types of graphs to be solved.
+pub trait Terminus: NodeTrait /* constraints on GraphMap */ + Drawn {
+    fn is_terminus(&self) -> Option<NonZero<AffiliationID>>;
+}
+
+/// Constraint on node and edge types of graphs to be solved, which may already be drawn as part of a path before solving.
+pub trait Drawn {
+    /// The affiliation this vertex or edge is drawn with, if any; every solution must agree.
+    fn drawn(&self) -> Option<NonZero<AffiliationID>>;
+}
+
+/// Reasons solving a graph may fail.
+#[derive(Debug)]
+pub enum SolverFailure {
+    /// The SAT solver detected a logical inconsistency, i.e. the graph as stated is unsolvable.
+    Inconsistent,
+    /// The board is unsolvable, and these features of it together are to blame.
+    ///
+    /// If paths cannot be drawn between the termini at all, even leaving cells empty, the features are the termini, walls, bridges, and so on which get in the way.
+    /// Otherwise, the paths can be drawn but not so as to fill the board, and the features are [`Filled`](Feature::Filled) locations which cannot all be covered.
+    /// Either way, the set is minimal: the board could be solved were any one of these features left out, and the others kept.
+    /// See [`Feature::locations`] for the [`Location`](crate::Location)s involved.
+    Conflict(Vec<Feature>),
+    /// The SAT solver could not solve the affiliation of at least one node and/or edge.
+    /// This should probably never happen.
+    NoAffFound,
+    /// The SAT [`Backend`](crate::backend::Backend) itself failed, e.g. because an external solver could not be run; the message says why.
+    Backend(String),
+    /// Solving ran past its [`deadline`](crate::SolveOptions::deadline) or [`conflict_budget`](crate::SolveOptions::conflict_budget).
+    ///
+    /// The SAT [`Backend`](crate::backend::Backend) which was stopped may be unusable, so solve again from a fresh one; see [`Backend::interruptible`](crate::backend::Backend::interruptible).
+    Timeout,
+    /// Solving was stopped by its [`cancel`](crate::SolveOptions::cancel) token.
+    ///
+    /// As with [`Self::Timeout`], the SAT [`Backend`](crate::backend::Backend) which was stopped may be unusable.
+    Cancelled,
+}
+
+/// Whether a graph has exactly one solution, as determined by [`GraphSolver::check_unique`](crate::solver::GraphSolver::check_unique) or [`Board::check_unique`](crate::Board::check_unique).
+#[derive(Debug)]
+pub enum Uniqueness<T> {
+    /// There is exactly one solution.
+    Unique(T),
+    /// There are at least two solutions; two of them are given.
+    Multiple(T, T),
+    /// There are no solutions.
+    Unsolvable,
+}
+
+/// The solved affiliation of every vertex and edge on a graph.
+pub type Solution<N, E> = HashMap<HasAffiliation<N, E>, AffiliationID>;
+
+/// A vertex or edge of a graph, either of which is assigned an affiliation when solving.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum HasAffiliation<N, E>
+where
+    N: Terminus,
+{
+    /// A vertex.
+    Node {
+        /// The vertex itself.
+        node: N,
+    },
+    /// An edge, identified by its weight and its endpoints.
+    Edge {
+        /// The edge weight.
+        edge: E,
+        /// The vertices this edge joins.
+        endpoints: UnorderedPair<N>,
+    },
+}
+
+impl<N, E> HasAffiliation<N, E>
+where
+    N: Terminus,
+    E: Copy,
+{
+    pub(crate) fn from_node(node: N) -> Self {
+        Self::Node { node }
+    }
+
+    pub(crate) fn from_edge(triple: (N, N, &E)) -> Self {
+        let (n1, n2, e) = triple;
+        Self::Edge { edge: *e, endpoints: UnorderedPair(n1, n2) }
+    }
+}
+
+/// A condition which holds if every listed selector (by index into [`Guards::selectors`]) is in the listed state.
+pub(crate) type Condition = Vec<(usize, bool)>;
+
+/// Conditions under which parts of a graph take part in solving, so that one encoding can describe a family of similar graphs.
+///
+/// Each selector is a SAT variable which is assumed to be in some state when solving.
+/// A vertex whose condition fails is absent; it and its incident edges are unaffiliated.
+/// An edge whose condition fails cannot be used, and its endpoints need not be joined even if they share an affiliation.
+/// A present vertex whose fill condition fails may be left unaffiliated.
+/// A Terminus whose condition fails is an ordinary vertex; one with no condition is a Terminus whenever it is present.
+/// A [`Drawn`] vertex or edge keeps its drawn affiliation only while its condition holds; one with no condition always keeps it.
+/// A vertex may not take an affiliation listed for it in `forbidden`, and must take one listed in `required`, while the condition listed alongside holds.
+/// Likewise, an edge listed in `directed` may only be travelled away from the listed endpoint, and one listed in `used` must be travelled.
+/// The path with an affiliation listed in `lengths` passes through a number of vertices within each range listed alongside, while its condition holds.
+#[derive(Clone)]
+pub struct Guards<N, E>
+where
+    N: Terminus,
+{
+    pub(crate) selectors: Vec<bool>,
+    pub(crate) nodes: HashMap<N, Condition>,
+    pub(crate) filled: HashMap<N, Condition>,
+    pub(crate) edges: HashMap<HasAffiliation<N, E>, Condition>,
+    pub(crate) termini: HashMap<N, Condition>,
+    pub(crate) drawn: HashMap<HasAffiliation<N, E>, Condition>,
+    pub(crate) forbidden: HashMap<N, Vec<(AffiliationID, Condition)>>,
+    pub(crate) required: HashMap<N, Vec<(AffiliationID, Condition)>>,
+    pub(crate) directed: HashMap<HasAffiliation<N, E>, Vec<(N, Condition)>>,
+    pub(crate) used: HashMap<HasAffiliation<N, E>, Vec<Condition>>,
+    pub(crate) lengths: HashMap<AffiliationID, Vec<(RangeInclusive<usize>, Condition)>>,
+}
+
+impl<N, E> Guards<N, E>
+where
+    N: Terminus,
+{
+    /// Whether `condition` holds with every selector in its given state; no condition always holds.
+    pub(crate) fn holds(&self, condition: Option<&Condition>) -> bool {
+        condition.is_none_or(|condition| condition.iter().all(|(selector, state)| self.selectors[*selector] == *state))
+    }
+}
+
+impl<N, E> Default for Guards<N, E>
+where
+    N: Terminus,
+{
+    fn default() -> Self {
+        Self {
+            selectors: Default::default(),
+            nodes: Default::default(),
+            filled: Default::default(),
+            edges: Default::default(),
+            termini: Default::default(),
+            drawn: Default::default(),
+            forbidden: Default::default(),
+            required: Default::default(),
+            directed: Default::default(),
+            used: Default::default(),
+            lengths: Default::default(),
+        }
+    }
+}
+
+/// Every square of `graph`: four vertices joined in a cycle with no edge across it, such as a 2x2 block of cells, each listed once.
+#[cfg(any(feature = "sat", feature = "search"))]
+pub(crate) fn squares<N: NodeTrait, E>(graph: &UnGraphMap<N, E>) -> Vec<[N; 4]> {
+    let mut squares = Vec::new();
+    for a in graph.nodes() {
+        // list each square from its least vertex, going first to the lesser of its neighbours there
+        for (b, d) in graph.neighbors(a).filter(|n| *n > a).tuple_combinations() {
+            let (b, d) = if b < d { (b, d) } else { (d, b) };
+            if graph.contains_edge(b, d) {
+                continue;
+            }
+            squares.extend(graph.neighbors(b)
+                .filter(|c| *c > a && graph.contains_edge(*c, d) && !graph.contains_edge(a, *c))
+                .map(|c| [a, b, c, d]));
+        }
+    }
+    squares
+}
@@ -21,26 +21,57 @@
 //!
 //! We make the following assertions in SAT form:
 //! 1. Every vertex is either a "terminus" (the origin of a flow) or a "path" (part of the path from one Terminus to another).
-//! All cells must be colored, so this vertex has some "affiliation" not equal to the null affiliation, 0.
-//! If V is a Terminus, exactly one incident edge has the same affiliation as V.
-//! Otherwise, exactly two incident edges have the same affiliation.
+//!    All cells must be colored, so this vertex has some "affiliation" not equal to the null affiliation, 0.
+//!    If V is a Terminus, exactly one incident edge has the same affiliation as V.
+//!    Otherwise, exactly two incident edges have the same affiliation.
 //! 2. Every edge either has affiliation 0, meaning its endpoints have different affiliations, or has a nonzero affiliation, meaning it shares an affiliation with its endpoints and is on the path from one identically affiliated Terminus to the other.
 //!
+//! These local assertions still admit closed loops of some affiliation which touch neither Terminus of that affiliation.
+//! Whenever the SAT solver produces such a loop, we add a clause forbidding it and solve again.
+//!
 //! We then solve and assign data to the graph accordingly.
+//! By default, [`varisat`] does the solving, but any SAT solver implementing [`Backend`](backend::Backend) may stand in for it.
 //! This is more performant than backtracking or graph algorithm based solutions.
+//!
+//! SAT solving, and with it [`varisat`], is behind the default `sat` feature.
+//! Without it, as for small WASM bundles, boards can still be built and, with the `search` feature, solved by backtracking with [`Board::solve_by_search`].
 
-pub use board::Board;
+pub use board::{Board, Hint, Settled};
 pub use builder::Builder;
 pub use location::Location;
+pub use shape::{MaybeSend, MaybeSync};
+#[cfg(feature = "sat")]
+pub use options::{AffiliationEncoding, AtMostOne, CancelToken, Objective, ProgressCallback, SolveOptions, SolveProgress, PROGRESS_INTERVAL};
+#[cfg(feature = "sat")]
+pub use session::Session;
+pub use graph::{SolverFailure, Uniqueness};
 
 pub(crate) mod board;
+#[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
 pub(crate) mod affiliation;
 pub(crate) mod location;
+#[cfg(feature = "sat")]
 pub(crate) mod logic;
+#[cfg(feature = "sat")]
+pub(crate) mod options;
+#[cfg(feature = "sat")]
+pub(crate) mod session;
 pub mod shape;
 pub(crate) mod cell;
 pub mod builder;
+#[cfg(feature = "sat")]
+pub mod backend;
+pub mod propagation;
+#[cfg(feature = "sat")]
+pub mod rating;
+#[cfg(feature = "sat")]
+pub mod trace;
+pub(crate) mod graph;
+#[cfg(feature = "sat")]
 pub(crate) mod solver;
+#[cfg(feature = "search")]
+pub(crate) mod search;
 #[cfg(feature = "wasm")]
 pub mod wasm;
@@ -2,11 +2,14 @@ use std::cmp::Ordering;
 use std::num::NonZero;
 
 use ndarray::Ix;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 type Coord = usize;
 pub(crate) type Dimension = NonZero<Coord>;
 
-#[derive(Clone, Eq, Hash, Copy, PartialEq, PartialOrd, Debug)]
+#[derive(Clone, Eq, Hash, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// A location `(x, y)` on a board. The top left corner is `Location(0, 0)`.
 pub struct Location(pub Coord, pub Coord);
 
@@ -16,6 +19,12 @@ impl Ord for Location {
     }
 }
 
+impl PartialOrd for Location {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Location {
     pub(crate) fn as_index(&self) -> (Coord, Coord) {
         (self.1, self.0)
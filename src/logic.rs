@@ -1,7 +1,12 @@
-use std::ops::Index;
+use std::ops::{Index, RangeInclusive};
 
 use itertools::Itertools;
-use varisat::Lit;
+use varisat::{Lit, Var};
+
+use crate::options::AtMostOne;
+
+/// Members of a group sharing one commander in [`AtMostOne::Commander`].
+const COMMANDER_GROUP_SIZE: usize = 3;
 
 fn invert(lit: Lit) -> Lit {
     match lit.is_negative() {
@@ -10,16 +15,210 @@ fn invert(lit: Lit) -> Lit {
     }
 }
 
-pub(crate) fn exactly_one(vars: Vec<Lit>) -> Vec<Vec<Lit>> {
-    let mut clauses = Vec::with_capacity(vars.len() * (vars.len() + 1) / 2 + 1);
+/// Take the next unused auxiliary variable.
+pub(crate) fn fresh_var(next_aux_var: &mut usize) -> Var {
+    let var = Var::from_index(*next_aux_var);
+    *next_aux_var += 1;
+    var
+}
+
+/// Generate clauses stating that exactly one of `vars` is true, with the at-most-one half encoded as `encoding` says.
+///
+/// Any auxiliary variables are taken starting from `next_aux_var`, which is advanced past them.
+pub(crate) fn exactly_one(vars: Vec<Lit>, encoding: AtMostOne, next_aux_var: &mut usize) -> Vec<Vec<Lit>> {
+    let mut clauses = at_most_one(&vars, encoding, next_aux_var);
 
+    // at least one var is true; A + B + C + ...
+    clauses.push(vars);
+
+    clauses
+}
+
+/// Generate clauses stating that at most one of `vars` is true.
+pub(crate) fn at_most_one(vars: &[Lit], encoding: AtMostOne, next_aux_var: &mut usize) -> Vec<Vec<Lit>> {
+    match encoding {
+        AtMostOne::Pairwise => pairwise(vars),
+        AtMostOne::Sequential => sequential(vars, next_aux_var),
+        AtMostOne::Commander => commander(vars, next_aux_var),
+    }
+}
+
+fn pairwise(vars: &[Lit]) -> Vec<Vec<Lit>> {
     // no two are true; (!A + !B) * (!A + !C) * ...
-    clauses.extend(vars.iter()
+    vars.iter()
         .combinations(2)
         .map(|pair| vec![invert(**pair.index(0)), invert(**pair.index(1))])
-    );
-    // at least one var is true; A + B + C + ...
-    clauses.push(vars);
+        .collect_vec()
+}
+
+fn sequential(vars: &[Lit], next_aux_var: &mut usize) -> Vec<Vec<Lit>> {
+    if vars.len() < 2 {
+        return Vec::new();
+    }
+
+    // S_i is true if any of X_1 through X_i is; then X_i must not be true if S_(i - 1) already is
+    let counters = (1..vars.len()).map(|_| fresh_var(next_aux_var)).collect_vec();
+    let mut clauses = Vec::with_capacity(3 * vars.len());
+
+    for (i, x) in vars.iter().enumerate() {
+        if let Some(s) = counters.get(i) {
+            // X_i => S_i
+            clauses.push(vec![!*x, s.positive()]);
+        }
+        if i > 0 {
+            let previous = counters[i - 1];
+            // X_i => !S_(i - 1)
+            clauses.push(vec![!*x, previous.negative()]);
+            if let Some(s) = counters.get(i) {
+                // S_(i - 1) => S_i
+                clauses.push(vec![previous.negative(), s.positive()]);
+            }
+        }
+    }
 
     clauses
-}
\ No newline at end of file
+}
+
+fn commander(vars: &[Lit], next_aux_var: &mut usize) -> Vec<Vec<Lit>> {
+    if vars.len() <= COMMANDER_GROUP_SIZE {
+        return pairwise(vars);
+    }
+
+    // split into groups, each with a commander which is true if any member is; at most one of each group is true, and so on for the commanders
+    let mut clauses = Vec::new();
+    let mut commanders = Vec::with_capacity(vars.len().div_ceil(COMMANDER_GROUP_SIZE));
+    for group in vars.chunks(COMMANDER_GROUP_SIZE) {
+        let c = fresh_var(next_aux_var);
+        clauses.extend(pairwise(group));
+        clauses.extend(group.iter().map(|x| vec![!*x, c.positive()]));
+        commanders.push(c.positive());
+    }
+    clauses.extend(commander(&commanders, next_aux_var));
+
+    clauses
+}
+
+/// Generate clauses stating that exactly one of `vars` is true by channelling them to a binary number, whose value is the index of the true one.
+///
+/// Each var is true exactly when the bits, which are fresh auxiliary variables, spell out its index; values past the last index are forbidden.
+pub(crate) fn binary_exactly_one(vars: &[Lit], next_aux_var: &mut usize) -> Vec<Vec<Lit>> {
+    if vars.len() <= 1 {
+        return vars.iter().map(|x| vec![*x]).collect_vec();
+    }
+
+    let width = usize::BITS - (vars.len() - 1).leading_zeros();
+    let bits = (0..width).map(|_| fresh_var(next_aux_var)).collect_vec();
+    // the literals which all hold when the bits spell `value`
+    let spelling = |value: usize| bits.iter()
+        .enumerate()
+        .map(|(place, bit)| bit.lit(value >> place & 1 == 1))
+        .collect_vec();
+
+    let mut clauses = Vec::new();
+    for (value, x) in vars.iter().enumerate() {
+        let spelled = spelling(value);
+        // X => every bit of its index
+        clauses.extend(spelled.iter().map(|bit| vec![!*x, *bit]));
+        // every bit of its index => X
+        clauses.push(spelled.iter().map(|bit| !*bit).chain([*x]).collect_vec());
+    }
+    for value in vars.len()..1 << width {
+        clauses.push(spelling(value).into_iter().map(|bit| !bit).collect_vec());
+    }
+    // implied by the above, but lets the solver see directly that the last remaining var must be true
+    clauses.push(vars.to_vec());
+
+    clauses
+}
+
+/// Generate clauses counting the true literals among `lits` in unary, up to `limit`, returning them along with the count.
+///
+/// The literal at index `j` of the count is true whenever more than `j` of `lits` are, so assuming it false allows at most `j` of them.
+/// This is the sequential counter of Sinz, with a register for each literal and each value up to `limit`.
+pub(crate) fn unary_count(lits: &[Lit], limit: usize, next_aux_var: &mut usize) -> (Vec<Vec<Lit>>, Vec<Lit>) {
+    if lits.is_empty() || limit == 0 {
+        return (Vec::new(), Vec::new());
+    }
+
+    // R_(i, j) is true if more than j of X_1 through X_i are
+    let registers = (0..lits.len())
+        .map(|_| (0..limit).map(|_| fresh_var(next_aux_var).positive()).collect_vec())
+        .collect_vec();
+    let mut clauses = Vec::with_capacity(2 * lits.len() * limit);
+
+    for (i, x) in lits.iter().enumerate() {
+        // X_i => R_(i, 0)
+        clauses.push(vec![!*x, registers[i][0]]);
+        if i == 0 {
+            continue;
+        }
+
+        for j in 0..limit {
+            // R_(i - 1, j) => R_(i, j)
+            clauses.push(vec![!registers[i - 1][j], registers[i][j]]);
+            if j > 0 {
+                // X_i and R_(i - 1, j - 1) => R_(i, j)
+                clauses.push(vec![!*x, !registers[i - 1][j - 1], registers[i][j]]);
+            }
+        }
+    }
+
+    (clauses, registers.into_iter().last().unwrap())
+}
+
+/// Generate clauses stating that the number of true literals among `lits` lies within `range`.
+///
+/// This is the same sequential counter as [`unary_count`], but with each register defined in both directions, so that the count is bounded below as well as above.
+/// Registers count only as high as the bounds need.
+pub(crate) fn cardinality(lits: &[Lit], range: RangeInclusive<usize>, next_aux_var: &mut usize) -> Vec<Vec<Lit>> {
+    let (min, max) = range.into_inner();
+    if min > max || min > lits.len() {
+        // no count will do
+        return vec![Vec::new()];
+    }
+
+    let limit = (max + 1).min(lits.len());
+    if limit == 0 || (min == 0 && max >= lits.len()) {
+        return Vec::new();
+    }
+
+    // R_(i, j) is true exactly when more than j of X_1 through X_i are
+    let registers = (0..lits.len())
+        .map(|_| (0..limit).map(|_| fresh_var(next_aux_var).positive()).collect_vec())
+        .collect_vec();
+    let mut clauses = Vec::with_capacity(4 * lits.len() * limit);
+
+    for (i, x) in lits.iter().enumerate() {
+        for j in 0..limit {
+            let r = registers[i][j];
+            match (i, j) {
+                // R_(0, 0) <=> X_0, and no more than one of a single literal is true
+                (0, 0) => clauses.extend([vec![!*x, r], vec![!r, *x]]),
+                (0, _) => clauses.push(vec![!r]),
+                // R_(i, 0) <=> R_(i - 1, 0) + X_i
+                (_, 0) => clauses.extend([
+                    vec![!registers[i - 1][0], r],
+                    vec![!*x, r],
+                    vec![!r, registers[i - 1][0], *x],
+                ]),
+                // R_(i, j) <=> R_(i - 1, j) + X_i * R_(i - 1, j - 1)
+                _ => clauses.extend([
+                    vec![!registers[i - 1][j], r],
+                    vec![!*x, !registers[i - 1][j - 1], r],
+                    vec![!r, registers[i - 1][j], *x],
+                    vec![!r, registers[i - 1][j], registers[i - 1][j - 1]],
+                ]),
+            }
+        }
+    }
+
+    let count = registers.last().unwrap();
+    if max < lits.len() {
+        clauses.push(vec![!count[max]]);
+    }
+    if min > 0 {
+        clauses.push(vec![count[min - 1]]);
+    }
+
+    clauses
+}
@@ -0,0 +1,178 @@
+//! Options controlling how a board is solved.
+
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "parallel")]
+use itertools::Itertools;
+use strum::VariantArray;
+
+/// How the solver should encode a board, passed to [`Board::solve`](crate::Board::solve).
+///
+/// Every combination of encodings has the same solutions, but they differ in the number of clauses and variables and so in how quickly a given board solves.
+#[derive(Clone, Debug)]
+pub struct SolveOptions {
+    /// How to state that at most one of several literals is true.
+    pub at_most_one: AtMostOne,
+    /// How to assign each vertex and edge exactly one affiliation.
+    pub affiliations: AffiliationEncoding,
+    /// Whether to [`propagate`](crate::Board::propagate) simple facts before solving and assume them; on by default.
+    pub propagate: bool,
+    /// A time after which to give up with [`SolverFailure::Timeout`](crate::SolverFailure::Timeout).
+    ///
+    /// [`Instant`] is unavailable on `wasm32-unknown-unknown`, so there, leave this and [`Self::progress`] unset and use [`Self::conflict_budget`] instead.
+    ///
+    /// Setting this or any other limit has [`varisat`] check its proof as it runs, so it can be stopped partway, which slows it by around half again; see [`Backend::interruptible`](crate::backend::Backend::interruptible).
+    pub deadline: Option<Instant>,
+    /// The most conflicts the SAT solver may run into, in total, before giving up with [`SolverFailure::Timeout`](crate::SolverFailure::Timeout).
+    ///
+    /// As with [`Self::deadline`], this slows [`varisat`] by around half again, even if the budget is never reached.
+    pub conflict_budget: Option<u64>,
+    /// A token which, once cancelled from any thread, stops solving with [`SolverFailure::Cancelled`](crate::SolverFailure::Cancelled).
+    pub cancel: Option<CancelToken>,
+    /// Called every [`PROGRESS_INTERVAL`] conflicts with how solving is going.
+    pub progress: Option<ProgressCallback>,
+    /// Shuffles the order in which vertices and edges are given variables, which changes how the SAT solver searches but not what it finds; 0, the default, keeps the order of the graph.
+    pub seed: u64,
+    /// How many differently configured solvers [`Board::solve`](crate::Board::solve) races on separate threads, taking the first answer and cancelling the rest; 1, the default, solves once.
+    ///
+    /// The first is configured as these options say, and the others each take a different [`Self::seed`] and cycle through the other encodings.
+    /// Their conflicts count together towards [`Self::conflict_budget`] and [`Self::progress`].
+    /// A [`Backend`](crate::backend::Backend) which cannot be interrupted, such as [`DimacsProcess`](crate::backend::DimacsProcess), keeps running to the end even once beaten.
+    /// Racing needs the `parallel` feature; without it, only the first solver runs.
+    /// Threads are unavailable on `wasm32-unknown-unknown`, so there, leave this at 1.
+    pub portfolio: usize,
+    /// Find a solution which is best by this measure, rather than any; each better solution found on the way costs another call to the SAT solver.
+    pub objective: Option<Objective>,
+}
+
+impl SolveOptions {
+    /// Whether any of the limits on solving are set, so that the SAT solver must be watched while it runs.
+    pub(crate) fn limited(&self) -> bool {
+        self.deadline.is_some() || self.conflict_budget.is_some() || self.cancel.is_some() || self.progress.is_some()
+    }
+
+    /// The options of each solver in the [`Self::portfolio`], beginning with these.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn racers(&self) -> Vec<SolveOptions> {
+        let encodings = AffiliationEncoding::VARIANTS.iter().cartesian_product(AtMostOne::VARIANTS).collect_vec();
+        let first = encodings.iter().position(|(affiliations, at_most_one)| **affiliations == self.affiliations && **at_most_one == self.at_most_one).unwrap();
+
+        (0..self.portfolio.max(1)).map(|racer| {
+            let (affiliations, at_most_one) = encodings[(first + racer) % encodings.len()];
+            Self {
+                affiliations: *affiliations,
+                at_most_one: *at_most_one,
+                seed: self.seed.wrapping_add(racer as u64),
+                portfolio: 1,
+                ..self.clone()
+            }
+        }).collect()
+    }
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        Self {
+            at_most_one: Default::default(),
+            affiliations: Default::default(),
+            propagate: true,
+            deadline: None,
+            conflict_budget: None,
+            cancel: None,
+            progress: None,
+            seed: 0,
+            portfolio: 1,
+            objective: None,
+        }
+    }
+}
+
+/// The number of conflicts between calls to [`SolveOptions::progress`].
+pub const PROGRESS_INTERVAL: u64 = 1000;
+
+/// A flag shared between threads which stops a solve once set; see [`SolveOptions::cancel`].
+///
+/// Clones share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create a token which is not yet cancelled.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Stop every solve watching this token at its next conflict.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`Self::cancel`] has been called on this token or any of its clones.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How a solve is going, as reported to [`SolveOptions::progress`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SolveProgress {
+    /// Conflicts the SAT solver has run into so far.
+    pub conflicts: u64,
+    /// Time since solving began.
+    pub elapsed: Duration,
+}
+
+/// A function to call with the [`SolveProgress`] of a solve; see [`SolveOptions::progress`].
+#[derive(Clone)]
+pub struct ProgressCallback(pub(crate) Arc<dyn Fn(SolveProgress) + Send + Sync>);
+
+impl ProgressCallback {
+    /// Wrap `callback`, which is called on the solving thread, so should return quickly.
+    pub fn new(callback: impl Fn(SolveProgress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+}
+
+impl Debug for ProgressCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// What to minimize among the solutions of a board; see [`SolveOptions::objective`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, VariantArray)]
+pub enum Objective {
+    /// The number of times a path changes direction, at the cells where it turns.
+    Bends,
+    /// The number of segments joining cells, summed over every path.
+    ///
+    /// Every cell is part of some path unless cells may be left empty, so only then do solutions differ in length.
+    Length,
+}
+
+/// Encodings of the constraint that at most one of a set of literals is true.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, VariantArray)]
+pub enum AtMostOne {
+    /// Forbid every pair of literals from both being true; quadratic in the number of literals, but adds no variables.
+    #[default]
+    Pairwise,
+    /// Use a sequential counter (the ladder encoding), with a linear number of clauses and variables.
+    Sequential,
+    /// Split the literals into small groups, each with a commander variable, and recurse on the commanders.
+    Commander,
+}
+
+/// Encodings of the affiliation of each vertex and edge.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, VariantArray)]
+pub enum AffiliationEncoding {
+    /// One variable for each affiliation, of which exactly one is true; the at-most-one half uses the [`AtMostOne`] encoding.
+    #[default]
+    OneHot,
+    /// A binary number giving the affiliation, with one variable for each affiliation defined from its bits.
+    ///
+    /// Each holder needs a logarithmic number of extra variables, but no clauses forbidding pairs of affiliations.
+    Binary,
+}
@@ -0,0 +1,195 @@
+//! Rules which force parts of a solution before the SAT solver runs, as in [`Board::propagate`](crate::Board::propagate).
+//!
+//! Many cells of a typical board are forced by simple local reasoning, the same as a person would use.
+//! Each fact found this way is handed to the SAT solver as a unit assumption, so it need not rediscover it.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use itertools::Itertools;
+use petgraph::graphmap::UnGraphMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use unordered_pair::UnorderedPair;
+
+use crate::affiliation::AffiliationID;
+use crate::location::Location;
+use crate::graph::{Drawn, HasAffiliation, Terminus};
+
+/// A rule by which propagation forces some fact.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Rule {
+    /// A cell on a path with only two open neighbours, such as one in a corner or at the end of a dead end, must connect to both.
+    TwoOpenNeighbours,
+    /// A terminus with only one open neighbour must connect to it.
+    OneOpenNeighbour,
+    /// A cell which already connects to as many neighbours as it may (two on a path, one for a terminus) connects to no others.
+    Saturated,
+    /// Two connected cells have the same affiliation, so a known affiliation carries across the connection.
+    Connected,
+    /// Two neighbouring cells with the same affiliation must connect, or else the path would touch itself.
+    SameAffiliation,
+    /// Two neighbouring cells with different affiliations cannot connect.
+    DifferentAffiliations,
+}
+
+/// A fact about every solution of a board.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Fact {
+    /// The path passes between these locations.
+    Connected(UnorderedPair<Location>),
+    /// No path passes between these locations.
+    Disconnected(UnorderedPair<Location>),
+    /// The cell at this location is on the path with this display character.
+    Affiliated(Location, char),
+}
+
+/// A [`Fact`] forced by a [`Rule`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Deduction {
+    /// The rule which fired.
+    pub rule: Rule,
+    /// What it forced.
+    pub fact: Fact,
+}
+
+/// A fact forced on a graph, in terms of its vertices and edges; see [`Fact`].
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Forced<N, E>
+where
+    N: Terminus,
+{
+    Used(Rule, HasAffiliation<N, E>),
+    Unused(Rule, HasAffiliation<N, E>),
+    Affiliated(Rule, N, AffiliationID),
+}
+
+/// What is known of some solution of a graph partway through propagation: whether some edges are used, and the affiliations of some vertices.
+#[derive(Clone)]
+pub(crate) struct Knowledge<N, E>
+where
+    N: Terminus,
+{
+    pub(crate) used: HashMap<HasAffiliation<N, E>, bool>,
+    pub(crate) affiliations: HashMap<N, AffiliationID>,
+}
+
+impl<N, E> Knowledge<N, E>
+where
+    N: Terminus,
+    E: Eq + Hash + Copy + Drawn,
+{
+    /// Know only the termini of `graph` and anything drawn on it.
+    pub(crate) fn new(graph: &UnGraphMap<N, E>) -> Self {
+        Self {
+            used: graph.all_edges()
+                .filter(|(_, _, e)| e.drawn().is_some())
+                .map(|edge_triple| (HasAffiliation::from_edge(edge_triple), true))
+                .collect(),
+            affiliations: graph.nodes()
+                .filter_map(|node| node.is_terminus().or(node.drawn()).map(|aff| (node, aff.get())))
+                .collect(),
+        }
+    }
+
+    /// The number of incident edges `vertex` must use: one for a Terminus, two otherwise.
+    #[inline]
+    pub(crate) fn degree(vertex: N) -> usize {
+        if vertex.is_terminus().is_some() { 1 } else { 2 }
+    }
+
+    /// Apply every [`Rule`] to `graph` until none fires, returning what was newly forced in the order it was found.
+    pub(crate) fn propagate(&mut self, graph: &UnGraphMap<N, E>) -> Vec<Forced<N, E>> {
+        let Self { used, affiliations } = self;
+        let mut forced = Vec::new();
+
+        loop {
+            let before = forced.len();
+
+            for vertex in graph.nodes() {
+                let incident = graph.edges(vertex).map(HasAffiliation::from_edge).collect_vec();
+                let degree = Self::degree(vertex);
+
+                let connected = incident.iter().filter(|edge| used.get(*edge) == Some(&true)).count();
+                let open = incident.iter().filter(|edge| !used.contains_key(*edge)).collect_vec();
+                if open.is_empty() {
+                    continue;
+                }
+
+                let (rule, state) = if connected >= degree {
+                    (Rule::Saturated, false)
+                } else if connected + open.len() == degree {
+                    (if degree == 1 { Rule::OneOpenNeighbour } else { Rule::TwoOpenNeighbours }, true)
+                } else {
+                    continue;
+                };
+
+                for edge in open {
+                    used.insert(*edge, state);
+                    forced.push(if state { Forced::Used(rule, *edge) } else { Forced::Unused(rule, *edge) });
+                }
+            }
+
+            for (n1, n2, e) in graph.all_edges() {
+                let edge = HasAffiliation::from_edge((n1, n2, e));
+
+                match (used.get(&edge), affiliations.get(&n1).copied(), affiliations.get(&n2).copied()) {
+                    (Some(true), Some(aff), None) | (Some(true), None, Some(aff)) => {
+                        let other = if affiliations.contains_key(&n1) { n2 } else { n1 };
+                        affiliations.insert(other, aff);
+                        forced.push(Forced::Affiliated(Rule::Connected, other, aff));
+                    }
+                    (None, Some(a1), Some(a2)) => {
+                        let state = a1 == a2;
+                        used.insert(edge, state);
+                        forced.push(match state {
+                            true => Forced::Used(Rule::SameAffiliation, edge),
+                            false => Forced::Unused(Rule::DifferentAffiliations, edge),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+
+            if forced.len() == before {
+                return forced;
+            }
+        }
+    }
+
+    /// Whether what is known breaks a rule which every solution follows, so that no solution agrees with it.
+    ///
+    /// Only local rules are checked, as in [`Self::propagate`]; knowledge which is not contradicted may still lead nowhere.
+    #[cfg(feature = "sat")]
+    pub(crate) fn contradicted(&self, graph: &UnGraphMap<N, E>) -> bool {
+        let overfull = graph.nodes().any(|vertex| {
+            let incident = graph.edges(vertex).map(HasAffiliation::from_edge).collect_vec();
+            let connected = incident.iter().filter(|edge| self.used.get(*edge) == Some(&true)).count();
+            let open = incident.iter().filter(|edge| !self.used.contains_key(*edge)).count();
+
+            connected > Self::degree(vertex) || connected + open < Self::degree(vertex)
+        });
+
+        let crossed = graph.all_edges().any(|(n1, n2, e)| {
+            self.used.get(&HasAffiliation::from_edge((n1, n2, e))) == Some(&true)
+                && matches!((self.affiliations.get(&n1), self.affiliations.get(&n2)), (Some(a1), Some(a2)) if a1 != a2)
+        });
+
+        overfull || crossed
+    }
+}
+
+/// Apply every [`Rule`] to `graph` until none fires, returning what was forced in the order it was found.
+///
+/// Every vertex must be filled, as in a graph solved by [`GraphSolver::solve`](crate::solver::GraphSolver::solve), and anything drawn is taken as given.
+/// If the graph is unsolvable, the facts may contradict each other; they are still sound, in that every solution would satisfy them.
+pub(crate) fn propagate<N, E>(graph: &UnGraphMap<N, E>) -> Vec<Forced<N, E>>
+where
+    N: Terminus,
+    E: Eq + Hash + Copy + Drawn,
+{
+    Knowledge::new(graph).propagate(graph)
+}
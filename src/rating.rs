@@ -0,0 +1,425 @@
+//! Difficulty ratings, which estimate how hard a board is for a person to solve, as in [`Board::rate`](crate::Board::rate).
+//!
+//! A board is rated by solving it the way a person might: with a catalogue of [`Technique`]s, always reaching for the simplest one which still makes progress.
+//! Each technique has a weight, and a board which needs heavier techniques more often scores higher.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use itertools::Itertools;
+use petgraph::graphmap::UnGraphMap;
+
+use crate::affiliation::AffiliationID;
+use crate::backend::Statistics;
+use crate::propagation::{Forced, Knowledge};
+use crate::graph::{Drawn, HasAffiliation, Solution, Terminus};
+
+/// The deepest [`Technique::Lookahead`] tried; anything harder counts as one step deeper for every edge left undecided.
+///
+/// Each level multiplies the work by the number of loose ends, so a large board which defeats depth 1 can take minutes to rate.
+const MAX_LOOKAHEAD: usize = 2;
+
+/// A deduction technique a person might use while solving, from simplest to hardest.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum Technique {
+    /// Any of the local [`Rule`](crate::propagation::Rule)s of propagation, such as a cell with only two open neighbours.
+    ForcedMove,
+    /// Reasoning about a region of empty cells: it must be filled by the only path which can reach it, and on a board whose cells can be coloured like a checkerboard, the paths through it must make up for any imbalance between the two colours.
+    RegionParity,
+    /// A cell which every route between the two loose ends of a path must pass through belongs to that path.
+    Bottleneck,
+    /// Supposing some connection is or is not made, and seeing that the simpler techniques then reach a contradiction.
+    Lookahead,
+}
+
+impl Technique {
+    /// How much one fact found by this technique adds to a [`Rating::score`].
+    pub fn weight(&self) -> usize {
+        match self {
+            Self::ForcedMove => 1,
+            Self::RegionParity => 3,
+            Self::Bottleneck => 5,
+            Self::Lookahead => 10,
+        }
+    }
+}
+
+/// An estimate of how hard a board is, as given by [`Board::rate`](crate::Board::rate).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rating {
+    /// The overall difficulty; higher is harder.
+    ///
+    /// Each fact found adds the [`weight`](Technique::weight) of the technique which found it, multiplied by the depth for [`Technique::Lookahead`].
+    /// If the techniques cannot finish the board, each connection left undecided adds as much as a lookahead one step deeper than any tried.
+    pub score: usize,
+    /// How many facts each technique found.
+    pub uses: HashMap<Technique, usize>,
+    /// The deepest [`Technique::Lookahead`] needed, or 0 if none was.
+    pub lookahead_depth: usize,
+    /// Whether the techniques alone decided every connection; never so for a board with more than one solution.
+    pub complete: bool,
+    /// What the SAT solver did to solve the board, if its backend keeps count; see [`Backend::statistics`](crate::backend::Backend::statistics).
+    ///
+    /// Counting is opt-in, since [`varisat`] only reports its conflicts while its proof is checked, which slows it by around half again.
+    /// To count them, rate with [`Board::rate_with_backend`](crate::Board::rate_with_backend) and a backend wrapped in [`Counted`](crate::backend::Counted), e.g. `|| Counted::new(Solver::new())`.
+    pub sat: Option<Statistics>,
+}
+
+/// What one step of solving found, in the order it was found.
+pub(crate) enum Progress<N, E>
+where
+    N: Terminus,
+{
+    /// Facts forced by the local rules of propagation; see [`Technique::ForcedMove`].
+    Forced(Vec<Forced<N, E>>),
+    /// Vertices filled with an affiliation by some other technique.
+    Filled(Technique, Vec<(N, AffiliationID)>),
+    /// An edge decided to be used or not, because the opposite led to a contradiction at this depth of lookahead.
+    Decided(HasAffiliation<N, E>, bool, usize),
+}
+
+/// A step of [`Rater::trace`]: progress made by the techniques, or else an edge decided by guessing.
+pub(crate) enum Traced<N, E>
+where
+    N: Terminus,
+{
+    Progress(Progress<N, E>),
+    Guessed(HasAffiliation<N, E>, bool),
+}
+
+/// What is known, having led to a contradiction.
+struct Contradiction;
+
+/// Applies the [`Technique`]s to a graph, every vertex of which must be filled.
+pub(crate) struct Rater<'gph, N, E>
+where
+    N: Terminus,
+{
+    graph: &'gph UnGraphMap<N, E>,
+    // which side of a two-colouring each vertex is on, if the graph has one
+    sides: Option<HashMap<N, bool>>,
+    // whether to apply any technique, or only guess
+    techniques: bool,
+}
+
+impl<'gph, N, E> From<&'gph UnGraphMap<N, E>> for Rater<'gph, N, E>
+where
+    N: Terminus,
+    E: Eq + Hash + Copy + Drawn,
+{
+    fn from(graph: &'gph UnGraphMap<N, E>) -> Self {
+        let mut sides = HashMap::with_capacity(graph.node_count());
+        let mut bipartite = true;
+
+        for root in graph.nodes() {
+            if sides.contains_key(&root) {
+                continue;
+            }
+
+            sides.insert(root, false);
+            let mut queue = VecDeque::from([root]);
+            while let Some(vertex) = queue.pop_front() {
+                for neighbour in graph.neighbors(vertex) {
+                    match sides.get(&neighbour) {
+                        None => {
+                            sides.insert(neighbour, !sides[&vertex]);
+                            queue.push_back(neighbour);
+                        }
+                        Some(side) => bipartite &= *side != sides[&vertex],
+                    }
+                }
+            }
+        }
+
+        Self {
+            graph,
+            sides: bipartite.then_some(sides),
+            techniques: true,
+        }
+    }
+}
+
+impl<N, E> Rater<'_, N, E>
+where
+    N: Terminus,
+    E: Eq + Hash + Copy + Drawn,
+{
+    /// Solve as far as the techniques allow and rate the result, leaving [`Rating::sat`] for the caller to fill in.
+    pub(crate) fn rate(&self) -> Rating {
+        let mut knowledge = Knowledge::new(self.graph);
+        let mut uses = HashMap::new();
+        let mut lookahead_depth = 0;
+        let mut score = 0;
+
+        // a contradiction means the board is unsolvable, which the caller has already ruled out
+        while let Ok(Some(progress)) = self.step(&mut knowledge, MAX_LOOKAHEAD) {
+            let (technique, facts, depth) = match progress {
+                Progress::Forced(forced) => (Technique::ForcedMove, forced.len(), 0),
+                Progress::Filled(technique, filled) => (technique, filled.len(), 0),
+                Progress::Decided(_, _, depth) => (Technique::Lookahead, 1, depth),
+            };
+            *uses.entry(technique).or_default() += facts;
+            lookahead_depth = lookahead_depth.max(depth);
+            score += technique.weight() * facts * depth.max(1);
+        }
+
+        let undecided = self.graph.edge_count() - knowledge.used.len();
+        score += Technique::Lookahead.weight() * (MAX_LOOKAHEAD + 1) * undecided;
+
+        Rating {
+            score,
+            uses,
+            lookahead_depth,
+            complete: undecided == 0,
+            sat: None,
+        }
+    }
+
+    /// Solve as far as the techniques allow, then guess the first undecided edge as `solution` has it, and so on until every edge is decided.
+    ///
+    /// `solution` must solve the graph; it decides nothing which the techniques can, since they only find what every solution agrees on.
+    pub(crate) fn trace(&self, solution: &Solution<N, E>) -> Vec<Traced<N, E>> {
+        let mut knowledge = Knowledge::new(self.graph);
+        let mut steps = Vec::new();
+
+        loop {
+            // anything consistent with a solution cannot be contradicted
+            if let Ok(Some(progress)) = self.step(&mut knowledge, MAX_LOOKAHEAD) {
+                steps.push(Traced::Progress(progress));
+                continue;
+            }
+
+            let Some(edge) = self.graph.all_edges()
+                .map(HasAffiliation::from_edge)
+                .find(|edge| !knowledge.used.contains_key(edge)) else {
+                return steps;
+            };
+
+            let state = solution[&edge] != 0;
+            knowledge.used.insert(edge, state);
+            steps.push(Traced::Guessed(edge, state));
+        }
+    }
+
+    /// Apply no techniques, e.g. because they do not hold for the graph, so that rating finds nothing and tracing only guesses.
+    pub(crate) fn guessing(self) -> Self {
+        Self {
+            techniques: false,
+            ..self
+        }
+    }
+
+    /// Apply the simplest technique which finds anything, looking ahead at most `max_depth` steps, and return what it found.
+    fn step(&self, knowledge: &mut Knowledge<N, E>, max_depth: usize) -> Result<Option<Progress<N, E>>, Contradiction> {
+        if !self.techniques {
+            return Ok(None);
+        }
+
+        let forced = knowledge.propagate(self.graph);
+        if knowledge.contradicted(self.graph) {
+            return Err(Contradiction);
+        }
+        if !forced.is_empty() {
+            return Ok(Some(Progress::Forced(forced)));
+        }
+
+        let filled = self.regions(knowledge)?;
+        if !filled.is_empty() {
+            return Ok(Some(Progress::Filled(Technique::RegionParity, filled)));
+        }
+
+        let filled = self.bottlenecks(knowledge)?;
+        if !filled.is_empty() {
+            return Ok(Some(Progress::Filled(Technique::Bottleneck, filled)));
+        }
+
+        for depth in 1..=max_depth {
+            if let Some((edge, state)) = self.look_ahead(knowledge, depth) {
+                return Ok(Some(Progress::Decided(edge, state, depth)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Apply techniques, looking ahead at most `max_depth` steps, until none finds anything.
+    fn settle(&self, knowledge: &mut Knowledge<N, E>, max_depth: usize) -> Result<(), Contradiction> {
+        while self.step(knowledge, max_depth)?.is_some() {}
+        Ok(())
+    }
+
+    /// The number of further incident edges `vertex` must use.
+    fn spare(&self, knowledge: &Knowledge<N, E>, vertex: N) -> usize {
+        let connected = self.graph.edges(vertex)
+            .filter(|edge_triple| knowledge.used.get(&HasAffiliation::from_edge(*edge_triple)) == Some(&true))
+            .count();
+        Knowledge::<N, E>::degree(vertex).saturating_sub(connected)
+    }
+
+    /// Whether the edge between `n1` and `n2` may yet be used.
+    fn open(&self, knowledge: &Knowledge<N, E>, n1: N, n2: N) -> bool {
+        let e = self.graph.edge_weight(n1, n2).unwrap();
+        knowledge.used.get(&HasAffiliation::from_edge((n1, n2, e))) != Some(&false)
+    }
+
+    /// Fill each region of unaffiliated vertices which only one path can reach, and check the balance of each region, returning the vertices filled.
+    fn regions(&self, knowledge: &mut Knowledge<N, E>) -> Result<Vec<(N, AffiliationID)>, Contradiction> {
+        let mut seen = HashSet::new();
+        let mut filled = Vec::new();
+
+        for root in self.graph.nodes() {
+            if knowledge.affiliations.contains_key(&root) || !seen.insert(root) {
+                continue;
+            }
+
+            let mut region = vec![root];
+            let mut queue = VecDeque::from([root]);
+            // the affiliated vertices a path could enter this region from, each with the edges it could enter through
+            let mut entrances: HashMap<N, usize> = HashMap::new();
+            while let Some(vertex) = queue.pop_front() {
+                for neighbour in self.graph.neighbors(vertex).filter(|neighbour| self.open(knowledge, vertex, *neighbour)) {
+                    if knowledge.affiliations.contains_key(&neighbour) {
+                        *entrances.entry(neighbour).or_default() += 1;
+                    } else if seen.insert(neighbour) {
+                        region.push(neighbour);
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+
+            let entrances = entrances.into_iter()
+                .map(|(vertex, edges)| (vertex, edges.min(self.spare(knowledge, vertex))))
+                .filter(|(_, slots)| *slots > 0)
+                .collect_vec();
+
+            // each path through the region alternates sides, so covers at most one more vertex of one side than the other, and uses two entrances
+            let imbalance = match &self.sides {
+                Some(sides) => region.iter().filter(|vertex| sides[*vertex]).count().abs_diff(region.iter().filter(|vertex| !sides[*vertex]).count()),
+                None => 0,
+            };
+            if 2 * imbalance.max(1) > entrances.iter().map(|(_, slots)| slots).sum() {
+                return Err(Contradiction);
+            }
+
+            let affs = entrances.iter().map(|(vertex, _)| knowledge.affiliations[vertex]).unique().collect_vec();
+            if let [aff] = affs[..] {
+                for vertex in region {
+                    knowledge.affiliations.insert(vertex, aff);
+                    filled.push((vertex, aff));
+                }
+            }
+        }
+
+        Ok(filled)
+    }
+
+    /// Fill every vertex which the only routes between the two loose ends of some path all pass through, returning the vertices filled.
+    fn bottlenecks(&self, knowledge: &mut Knowledge<N, E>) -> Result<Vec<(N, AffiliationID)>, Contradiction> {
+        let ends = self.graph.nodes()
+            .filter(|vertex| knowledge.affiliations.contains_key(vertex) && self.spare(knowledge, *vertex) > 0)
+            .into_group_map_by(|vertex| knowledge.affiliations[vertex]);
+        let mut filled = Vec::new();
+
+        for (aff, ends) in ends.into_iter().sorted_by_key(|(aff, _)| *aff) {
+            let [from, to] = ends[..] else {
+                continue;
+            };
+
+            let Some(separators) = self.separators(knowledge, from, to) else {
+                return Err(Contradiction);
+            };
+            for vertex in separators {
+                knowledge.affiliations.insert(vertex, aff);
+                filled.push((vertex, aff));
+            }
+
+            if !filled.is_empty() {
+                return Ok(filled);
+            }
+        }
+
+        Ok(filled)
+    }
+
+    /// Find the unaffiliated vertices which every route from `from` to `to` through unaffiliated vertices passes through, or [`None`] if there is no such route.
+    ///
+    /// These are the cut vertices between the two, found by depth-first search from `from`, as in Tarjan's algorithm for articulation points.
+    fn separators(&self, knowledge: &Knowledge<N, E>, from: N, to: N) -> Option<Vec<N>> {
+        let mut discovered = HashMap::from([(from, 0)]);
+        // the earliest vertex reachable from each vertex's subtree by one edge which is not a tree edge
+        let mut low = HashMap::from([(from, 0)]);
+        let mut parents: HashMap<N, N> = HashMap::new();
+        let mut stack = vec![(from, self.graph.neighbors(from).collect_vec().into_iter())];
+
+        while let Some((vertex, neighbours)) = stack.last_mut() {
+            let vertex = *vertex;
+            match neighbours.next() {
+                Some(neighbour) => {
+                    if !self.open(knowledge, vertex, neighbour) || parents.get(&vertex) == Some(&neighbour) {
+                        continue;
+                    }
+
+                    match discovered.get(&neighbour) {
+                        Some(order) => {
+                            let order = *order;
+                            low.entry(vertex).and_modify(|low| *low = order.min(*low));
+                        }
+                        // no simple route passes through `to` on its way to `to`, so it is searched through like any other vertex
+                        None if neighbour == to || !knowledge.affiliations.contains_key(&neighbour) => {
+                            let order = discovered.len();
+                            discovered.insert(neighbour, order);
+                            low.insert(neighbour, order);
+                            parents.insert(neighbour, vertex);
+                            stack.push((neighbour, self.graph.neighbors(neighbour).collect_vec().into_iter()));
+                        }
+                        None => {}
+                    }
+                }
+                None => {
+                    stack.pop();
+                    if let Some(parent) = parents.get(&vertex) {
+                        let child_low = low[&vertex];
+                        low.entry(*parent).and_modify(|low| *low = child_low.min(*low));
+                    }
+                }
+            }
+        }
+
+        if !discovered.contains_key(&to) {
+            return None;
+        }
+
+        // walking back from `to`, a vertex separates it from `from` if nothing below it on the way reaches above it
+        let mut separators = Vec::new();
+        let mut child = to;
+        while let Some(vertex) = parents.get(&child).copied().filter(|vertex| *vertex != from) {
+            if low[&child] >= discovered[&vertex] {
+                separators.push(vertex);
+            }
+            child = vertex;
+        }
+
+        Some(separators)
+    }
+
+    /// Find an undecided edge which, supposed used or unused, leads to a contradiction within `depth - 1` further steps of lookahead, and decide it the other way, returning it and whether it is used.
+    fn look_ahead(&self, knowledge: &mut Knowledge<N, E>, depth: usize) -> Option<(HasAffiliation<N, E>, bool)> {
+        for edge_triple in self.graph.all_edges() {
+            let edge = HasAffiliation::from_edge(edge_triple);
+            if knowledge.used.contains_key(&edge) {
+                continue;
+            }
+
+            for state in [true, false] {
+                let mut supposed = knowledge.clone();
+                supposed.used.insert(edge, state);
+                if self.settle(&mut supposed, depth - 1).is_err() {
+                    knowledge.used.insert(edge, !state);
+                    return Some((edge, !state));
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,397 @@
+//! A solver which searches for paths directly on the graph, without any SAT solver.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops::RangeInclusive;
+
+use itertools::Itertools;
+use petgraph::graphmap::UnGraphMap;
+
+use crate::affiliation::AffiliationID;
+use crate::builder::SelfTouch;
+use crate::graph::{squares, Condition, Drawn, Guards, HasAffiliation, Solution, SolverFailure, Terminus};
+
+/// Solves a Numberlink graph by growing a path from one terminus of each pair, one cell at a time, and backtracking.
+///
+/// The rules are the same as for [`GraphSolver`](crate::solver::GraphSolver): every vertex is filled unless it is optional, and unless the rules say otherwise, two neighbouring vertices are joined exactly when they have the same affiliation, so no path touches itself.
+/// Restrictions, checkpoints, one-way edges and lengths given by [`Self::with_guards`] are kept to as well.
+/// At every step, the path with the fewest ways forward is extended, and the search backs out as soon as
+/// - some pair can no longer be joined through empty vertices,
+/// - some region of empty vertices, not all optional, cannot be reached by any path which could still pass through it, or
+/// - some empty vertex which is not optional has fewer than two neighbours it could still be joined to, or
+/// - some [`Drawn`] vertex or edge has been given another affiliation.
+pub(crate) struct GraphSearch<'gph, N, E>
+where
+    N: Terminus,
+{
+    graph: &'gph UnGraphMap<N, E>,
+    nodes: Vec<N>,
+    // the neighbours of each vertex, along with the index of the edge to each
+    adjacency: Vec<Vec<(usize, usize)>>,
+    // the endpoints of each edge, in the order of `graph.all_edges()`
+    edges: Vec<(usize, usize)>,
+    // the termini of each affiliation which has them, as (start, target)
+    termini: HashMap<AffiliationID, (usize, usize)>,
+    // the affiliation each vertex and edge is drawn with, or 0 if it is not
+    drawn_nodes: Vec<AffiliationID>,
+    drawn_edges: Vec<AffiliationID>,
+    // whether each vertex may be left empty
+    optional: Vec<bool>,
+    // the affiliations each vertex may not take, and those it must
+    forbidden: Vec<Vec<AffiliationID>>,
+    required: Vec<Vec<AffiliationID>>,
+    // for each edge, the vertices it may only be crossed away from, and whether it must be crossed
+    directed: Vec<Vec<usize>>,
+    crossed: Vec<bool>,
+    // the number of vertices the path of each affiliation may pass through, where limited
+    lengths: HashMap<AffiliationID, RangeInclusive<usize>>,
+    self_touch: SelfTouch,
+    // the squares each vertex is part of, which a path may not fill if it may not run in a block
+    squares: Vec<Vec<[usize; 4]>>,
+}
+
+/// How far the search has got.
+#[derive(Clone)]
+struct State {
+    // the affiliation of each vertex, or 0 while it is empty
+    affiliations: Vec<AffiliationID>,
+    used: Vec<bool>,
+    // the end of the path grown so far for each unfinished affiliation
+    heads: HashMap<AffiliationID, usize>,
+    // for each affiliation whose one-way edges say which way it travels, whether it is from its start to its target
+    orientations: HashMap<AffiliationID, bool>,
+    // the number of vertices the path of each affiliation passes through so far
+    lengths: HashMap<AffiliationID, usize>,
+}
+
+impl<'gph, N, E> From<&'gph UnGraphMap<N, E>> for GraphSearch<'gph, N, E>
+where
+    N: Terminus,
+    E: Eq + Hash + Copy + Drawn,
+{
+    fn from(graph: &'gph UnGraphMap<N, E>) -> Self {
+        let nodes = graph.nodes().collect_vec();
+        let indices: HashMap<N, usize> = nodes.iter().enumerate().map(|(index, node)| (*node, index)).collect();
+        let edges = graph.all_edges().map(|(n1, n2, _)| (indices[&n1], indices[&n2])).collect_vec();
+
+        let mut adjacency = vec![Vec::new(); nodes.len()];
+        for (index, (v1, v2)) in edges.iter().enumerate() {
+            adjacency[*v1].push((*v2, index));
+            adjacency[*v2].push((*v1, index));
+        }
+
+        let termini = nodes.iter()
+            .enumerate()
+            .filter_map(|(index, node)| node.is_terminus().map(|aff| (aff.get(), index)))
+            .into_group_map()
+            .into_iter()
+            .map(|(aff, ends)| (aff, match ends[..] {
+                [start, target] => (start, target),
+                // anything but a pair of termini can never be joined, which the search discovers for itself
+                _ => (ends[0], usize::MAX),
+            }))
+            .collect();
+
+        let drawn_nodes = nodes.iter().map(|node| node.drawn().map_or(0, |aff| aff.get())).collect();
+        let drawn_edges = graph.all_edges().map(|(_, _, e)| e.drawn().map_or(0, |aff| aff.get())).collect();
+
+        let mut vertex_squares = vec![Vec::new(); nodes.len()];
+        for square in squares(graph) {
+            let square = square.map(|vertex| indices[&vertex]);
+            square.iter().for_each(|vertex| vertex_squares[*vertex].push(square));
+        }
+
+        Self {
+            graph,
+            nodes,
+            adjacency,
+            edges,
+            termini,
+            drawn_nodes,
+            drawn_edges,
+            optional: vec![false; graph.node_count()],
+            forbidden: vec![Vec::new(); graph.node_count()],
+            required: vec![Vec::new(); graph.node_count()],
+            directed: vec![Vec::new(); graph.edge_count()],
+            crossed: vec![false; graph.edge_count()],
+            lengths: HashMap::new(),
+            self_touch: Default::default(),
+            squares: vertex_squares,
+        }
+    }
+}
+
+impl<N, E> GraphSearch<'_, N, E>
+where
+    N: Terminus,
+    E: Eq + Hash + Copy + Drawn,
+{
+    /// Allow each vertex whose fill condition in `guards` fails to be left empty, and keep to each restriction and direction whose condition holds.
+    ///
+    /// Only [`Guards::filled`], [`Guards::forbidden`], [`Guards::required`], [`Guards::directed`], [`Guards::used`] and [`Guards::lengths`] are read; every vertex and edge of the graph takes part.
+    pub(crate) fn with_guards(self, guards: &Guards<N, E>) -> Self {
+        let indices: HashMap<N, usize> = self.nodes.iter().enumerate().map(|(index, node)| (*node, index)).collect();
+        let edges = self.graph.all_edges().map(HasAffiliation::from_edge).collect_vec();
+        let directed = edges.iter()
+            .map(|edge| guards.directed.get(edge).into_iter()
+                .flatten()
+                .filter(|(_, condition)| guards.holds(Some(condition)))
+                .map(|(from, _)| indices[from])
+                .collect_vec())
+            .collect();
+        let crossed = edges.iter()
+            .map(|edge| guards.used.get(edge).is_some_and(|conditions| conditions.iter().any(|condition| guards.holds(Some(condition)))))
+            .collect();
+        // a path kept to several lengths is kept to all of them at once
+        let lengths = guards.lengths.iter()
+            .filter_map(|(aff, lengths)| lengths.iter()
+                .filter(|(_, condition)| guards.holds(Some(condition)))
+                .map(|(length, _)| length.clone())
+                .reduce(|l1, l2| *l1.start().max(l2.start())..=*l1.end().min(l2.end()))
+                .map(|length| (*aff, length)))
+            .collect();
+
+        let restricted = |restrictions: &HashMap<N, Vec<(AffiliationID, Condition)>>| self.nodes.iter()
+            .map(|node| restrictions.get(node).into_iter()
+                .flatten()
+                .filter(|(_, condition)| guards.holds(Some(condition)))
+                .map(|(aff, _)| *aff)
+                .collect_vec())
+            .collect_vec();
+
+        Self {
+            optional: self.nodes.iter().map(|node| !guards.holds(guards.filled.get(node))).collect(),
+            forbidden: restricted(&guards.forbidden),
+            required: restricted(&guards.required),
+            directed,
+            crossed,
+            lengths,
+            ..self
+        }
+    }
+
+    /// Allow paths to touch themselves as `self_touch` says, rather than in no way.
+    pub(crate) fn with_self_touch(self, self_touch: SelfTouch) -> Self {
+        Self {
+            self_touch,
+            ..self
+        }
+    }
+
+    /// Search for a solution, returning [`SolverFailure::Inconsistent`] if there is none.
+    pub(crate) fn solve(&self) -> Result<Solution<N, E>, SolverFailure> {
+        let mut state = State {
+            affiliations: self.nodes.iter().map(|node| node.is_terminus().map_or(0, |aff| aff.get())).collect(),
+            used: vec![false; self.edges.len()],
+            heads: HashMap::new(),
+            orientations: HashMap::new(),
+            lengths: HashMap::new(),
+        };
+
+        for (aff, (start, target)) in self.termini.iter().sorted() {
+            state.heads.insert(*aff, *start);
+            state.lengths.insert(*aff, 1);
+            // termini next to each other must be joined straight away, unless the path may touch itself
+            if !self.self_touch.orthogonal {
+                continue;
+            }
+            if let Some((_, edge)) = self.adjacency[*start].iter().find(|(neighbour, _)| neighbour == target) {
+                let oriented = self.orientation(None, *start, *edge).ok_or(SolverFailure::Inconsistent)?;
+                state.orientations.extend(oriented.map(|oriented| (*aff, oriented)));
+                state.used[*edge] = true;
+                state.heads.remove(aff);
+                state.lengths.insert(*aff, 2);
+            }
+        }
+
+        let state = self.search(state).ok_or(SolverFailure::Inconsistent)?;
+
+        let mut solution: Solution<N, E> = self.nodes.iter()
+            .zip(&state.affiliations)
+            .map(|(node, aff)| (HasAffiliation::from_node(*node), *aff))
+            .collect();
+        for (index, edge_triple) in self.graph.all_edges().enumerate() {
+            let aff = if state.used[index] { state.affiliations[self.edges[index].0] } else { 0 };
+            solution.insert(HasAffiliation::from_edge(edge_triple), aff);
+        }
+
+        Ok(solution)
+    }
+
+    fn search(&self, state: State) -> Option<State> {
+        if !self.viable(&state) {
+            return None;
+        }
+
+        // extend the path with the fewest ways forward
+        let Some((aff, moves)) = state.heads.keys()
+            .sorted()
+            .map(|aff| (*aff, self.moves(&state, *aff)))
+            .min_by_key(|(_, moves)| moves.len())
+        else {
+            let filled = state.affiliations.iter().zip(&self.optional).all(|(aff, optional)| *aff != 0 || *optional);
+            let drawn = self.drawn_edges.iter().zip(&state.used).all(|(drawn, used)| *drawn == 0 || *used);
+            let passed = self.required.iter().zip(&state.affiliations).all(|(required, aff)| required.iter().all(|required| required == aff));
+            let crossed = self.crossed.iter().zip(&state.used).all(|(crossed, used)| !crossed || *used);
+            let long_enough = self.lengths.iter().all(|(aff, length)| state.lengths.get(aff).is_some_and(|so_far| length.contains(so_far)));
+            return (filled && drawn && passed && crossed && long_enough).then_some(state);
+        };
+
+        moves.into_iter().find_map(|(next, edge)| self.search(self.advance(&state, aff, next, edge)))
+    }
+
+    /// The vertices the head of `aff` may move to next, along with the edges to them.
+    fn moves(&self, state: &State, aff: AffiliationID) -> Vec<(usize, usize)> {
+        let head = state.heads[&aff];
+        let target = self.termini[&aff].1;
+
+        self.adjacency[head].iter()
+            .copied()
+            .filter(|(next, _)| !self.forbidden[*next].contains(&aff) && self.required[*next].iter().all(|required| *required == aff))
+            .filter(|(next, edge)| self.crossing(state, aff, *next, *edge).is_some())
+            // moving to `next`, and on to the target if that finishes the path, must not make the path too long
+            .filter(|(next, _)| self.lengths.get(&aff)
+                .is_none_or(|length| state.lengths[&aff] + 1 + usize::from(self.finishing(aff, *next).is_some()) <= *length.end()))
+            .filter(|(next, _)| match self.self_touch.orthogonal {
+                // joining a vertex next to some other part of this path would make the path touch itself, except at the target, where the path ends
+                true => state.affiliations[*next] == 0 && self.adjacency[*next].iter()
+                    .all(|(neighbour, _)| *neighbour == head || *neighbour == target || state.affiliations[*neighbour] != aff),
+                // otherwise the path ends only once it moves onto its target
+                false => *next == target || state.affiliations[*next] == 0 && !self.fills_square(state, aff, *next),
+            })
+            .collect_vec()
+    }
+
+    /// Whether giving `vertex` the affiliation `aff` would fill a square with it, where paths may not run in a block.
+    fn fills_square(&self, state: &State, aff: AffiliationID, vertex: usize) -> bool {
+        self.self_touch.blocks && self.squares[vertex].iter()
+            .any(|square| square.iter().all(|other| *other == vertex || state.affiliations[*other] == aff))
+    }
+
+    /// How a path must travel to cross `edge` away from `from` as it grows, given `oriented`, how it must travel so far:
+    /// `Some(true)` from its start to its target, `Some(false)` the other way, or [`None`] either way.
+    ///
+    /// Returns [`None`] if the path cannot cross `edge` so.
+    fn orientation(&self, oriented: Option<bool>, from: usize, edge: usize) -> Option<Option<bool>> {
+        match self.directed[edge].iter().map(|start| *start == from).dedup().collect_vec()[..] {
+            [] => Some(oriented),
+            [forward] if oriented.is_none_or(|oriented| oriented == forward) => Some(Some(forward)),
+            _ => None,
+        }
+    }
+
+    /// How the path of `aff` must travel once its head moves to `next` along `edge`, along with the edge to its target if it then finishes, as in [`Self::orientation`].
+    fn crossing(&self, state: &State, aff: AffiliationID, next: usize, edge: usize) -> Option<Option<bool>> {
+        let oriented = self.orientation(state.orientations.get(&aff).copied(), state.heads[&aff], edge)?;
+        match self.finishing(aff, next) {
+            Some(last) => self.orientation(oriented, next, last),
+            None => Some(oriented),
+        }
+    }
+
+    /// The edge along which the path of `aff` finishes as soon as its head reaches `next`, which is next to its target, where the path may not touch itself.
+    fn finishing(&self, aff: AffiliationID, next: usize) -> Option<usize> {
+        let target = self.termini[&aff].1;
+        match next != target && self.self_touch.orthogonal {
+            true => self.adjacency[next].iter().find(|(neighbour, _)| *neighbour == target).map(|(_, edge)| *edge),
+            false => None,
+        }
+    }
+
+    /// Move the head of `aff` to `next` along `edge`, finishing the path if `next` is its target, or is next to it where the path may not touch itself.
+    fn advance(&self, state: &State, aff: AffiliationID, next: usize, edge: usize) -> State {
+        let oriented = self.crossing(state, aff, next, edge).flatten();
+        let mut state = state.clone();
+        state.affiliations[next] = aff;
+        state.used[edge] = true;
+        state.heads.insert(aff, next);
+        state.orientations.extend(oriented.map(|oriented| (aff, oriented)));
+        *state.lengths.get_mut(&aff).unwrap() += 1;
+
+        if next == self.termini[&aff].1 {
+            state.heads.remove(&aff);
+        } else if let Some(last) = self.finishing(aff, next) {
+            state.used[last] = true;
+            state.heads.remove(&aff);
+            *state.lengths.get_mut(&aff).unwrap() += 1;
+        }
+
+        state
+    }
+
+    /// Check that `state` could still lead to a solution, as far as can be cheaply determined.
+    fn viable(&self, state: &State) -> bool {
+        // a drawn vertex, or a drawn edge once both its vertices are affiliated, has the affiliation it was drawn with; two neighbours with the same affiliation are always joined
+        let misdrawn_node = (0..self.nodes.len())
+            .any(|vertex| self.drawn_nodes[vertex] != 0 && ![0, self.drawn_nodes[vertex]].contains(&state.affiliations[vertex]));
+        let misdrawn_edge = self.edges.iter()
+            .zip(&self.drawn_edges)
+            .filter(|(_, drawn)| **drawn != 0)
+            .any(|((v1, v2), drawn)| state.affiliations[*v1] != 0 && state.affiliations[*v2] != 0
+                && (state.affiliations[*v1] != *drawn || state.affiliations[*v2] != *drawn));
+        if misdrawn_node || misdrawn_edge {
+            return false;
+        }
+
+        let targets = state.heads.keys().map(|aff| self.termini[aff].1).collect_vec();
+        let open = |vertex: usize| state.affiliations[vertex] == 0
+            || state.heads.values().contains(&vertex)
+            || targets.contains(&vertex);
+
+        // a dead end: an empty vertex must be joined to two neighbours
+        let dead_end = (0..self.nodes.len())
+            .filter(|vertex| state.affiliations[*vertex] == 0 && !self.optional[*vertex])
+            .any(|vertex| self.adjacency[vertex].iter().filter(|(neighbour, _)| open(*neighbour)).count() < 2);
+        if dead_end {
+            return false;
+        }
+
+        // label each region of connected empty vertices, noting which must be filled
+        let mut regions = vec![usize::MAX; self.nodes.len()];
+        let mut required = Vec::new();
+        let mut num_regions = 0;
+        for root in 0..self.nodes.len() {
+            if state.affiliations[root] != 0 || regions[root] != usize::MAX {
+                continue;
+            }
+
+            regions[root] = num_regions;
+            required.push(false);
+            let mut queue = VecDeque::from([root]);
+            while let Some(vertex) = queue.pop_front() {
+                required[num_regions] |= !self.optional[vertex];
+                for (neighbour, _) in &self.adjacency[vertex] {
+                    if state.affiliations[*neighbour] == 0 && regions[*neighbour] == usize::MAX {
+                        regions[*neighbour] = num_regions;
+                        queue.push_back(*neighbour);
+                    }
+                }
+            }
+            num_regions += 1;
+        }
+
+        // each unfinished path runs through a region next to both its head and its target, and each region must have some path run through it
+        let touching = |vertex: usize| self.adjacency[vertex].iter()
+            .filter(|(neighbour, _)| state.affiliations[*neighbour] == 0)
+            .map(|(neighbour, _)| regions[*neighbour])
+            .collect_vec();
+        let mut visited = vec![false; num_regions];
+        for (aff, head) in &state.heads {
+            let target = self.termini[aff].1;
+            if target == usize::MAX {
+                return false;
+            }
+
+            let from_target = touching(target);
+            let through = touching(*head).into_iter().filter(|region| from_target.contains(region)).collect_vec();
+            // where the path may touch itself, it need not end as soon as it reaches its target
+            let arrived = !self.self_touch.orthogonal && self.adjacency[*head].iter().any(|(neighbour, _)| *neighbour == target);
+            if through.is_empty() && !arrived {
+                return false;
+            }
+            through.into_iter().for_each(|region| visited[region] = true);
+        }
+
+        visited.into_iter().zip(required).all(|(visited, required)| visited || !required)
+    }
+}
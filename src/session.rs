@@ -0,0 +1,107 @@
+//! Sessions, which keep one SAT solver alive while the features of a board are switched on and off.
+
+use varisat::{Lit, Solver};
+
+use crate::board::{Board, Features};
+use crate::builder::Feature;
+use crate::location::Dimension;
+use crate::options::SolveOptions;
+use crate::shape::FullShape;
+use crate::graph::SolverFailure;
+
+/// A board under editing, which can be solved again after every change without encoding it from scratch.
+///
+/// Every feature placed by the [`Builder`](crate::Builder) the board came from, whether a pair of termini, bridge, warp, dropped location, or wall, has an activation literal in one SAT instance.
+/// Switching a feature on or off with [`Self::set_enabled`] only changes which of these literals are assumed, so each call to [`Self::solve`] reuses the clauses and everything the solver has learned so far.
+///
+/// Only features placed by the builder can be switched; to offer others later, place them up front and switch them off.
+pub struct Session<Sh>
+where
+    Sh: FullShape,
+{
+    dims: (Dimension, Dimension),
+    affiliation_displays: Vec<char>,
+    features: Features<Sh>,
+    solver: Solver<'static>,
+    // assumptions which hold however the features are switched
+    fixed_assumptions: Vec<Lit>,
+}
+
+impl<Sh> From<Board<Sh>> for Session<Sh>
+where
+    Sh: FullShape,
+{
+    /// Start a session from an unsolved board, with every feature switched on.
+    fn from(board: Board<Sh>) -> Self {
+        let mut solver = Solver::new();
+        let (fixed_assumptions, _) = board.features.solver().load(&mut solver);
+
+        Self {
+            dims: board.dims,
+            affiliation_displays: board.affiliation_displays,
+            features: board.features,
+            solver,
+            fixed_assumptions,
+        }
+    }
+}
+
+impl<Sh> Session<Sh>
+where
+    Sh: FullShape,
+{
+    /// Every feature which can be switched on and off, including a [`Feature::Filled`] for every location.
+    pub fn features(&self) -> &[Feature] {
+        &self.features.list
+    }
+
+    /// Whether `feature` is switched on, or [`None`] if it is not part of this session.
+    pub fn is_enabled(&self, feature: &Feature) -> Option<bool> {
+        self.selector_of(feature).map(|selector| self.features.guards.selectors[selector])
+    }
+
+    /// Switch `feature` on or off, returning `false` and changing nothing if it is not part of this session.
+    ///
+    /// Switching off a [`Feature::Filled`] allows its location to be left empty.
+    pub fn set_enabled(&mut self, feature: &Feature, enabled: bool) -> bool {
+        match self.selector_of(feature) {
+            None => false,
+            Some(selector) => {
+                self.features.guards.selectors[selector] = enabled;
+                true
+            }
+        }
+    }
+
+    /// The board as its features are currently switched, unsolved.
+    pub fn board(&self) -> Board<Sh> {
+        Board {
+            graph: self.features.graph(None),
+            dims: self.dims,
+            affiliation_displays: self.affiliation_displays.clone(),
+            features: self.features.clone(),
+        }
+    }
+
+    /// Solve the board as its features are currently switched.
+    ///
+    /// Returns the solved board, or fails as [`Board::solve`] does; explaining an unsolvable board needs fresh SAT instances, so takes longer than solving.
+    pub fn solve(&mut self) -> Result<Board<Sh>, SolverFailure> {
+        let graph_solver = self.features.solver();
+        graph_solver.assume_selectors(&mut self.solver, &self.fixed_assumptions);
+
+        let board = self.board();
+        match graph_solver.next_solution(&mut self.solver)? {
+            Some(solution) => Ok(Board {
+                graph: self.features.graph(Some(&solution)),
+                ..board
+            }),
+            None => Err(board.explain(&SolveOptions::default(), Solver::new)),
+        }
+    }
+
+    #[inline]
+    fn selector_of(&self, feature: &Feature) -> Option<usize> {
+        self.features.list.iter().position(|other| other == feature)
+    }
+}
@@ -1,3 +1,5 @@
+//! Board shapes, i.e. the geometry by which cells neighbor one another.
+
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::num::NonZero;
@@ -15,7 +17,7 @@ use crate::location::{Dimension, Location};
 /// Functionality that must be implemented on a case-by-case basis for any board shape.
 ///
 /// [`SquareStep`] and [`HexStep`] are built-in implementations.
-pub trait Shape: Sized + Copy + VariantArray + PartialEq + Eq + Hash + Ord + PartialOrd {
+pub trait Shape: Sized + Copy + MaybeSend + MaybeSync + VariantArray + PartialEq + Eq + Hash + Ord + PartialOrd {
     /// Attempt the step from `location` in the direction specified by `self` and return the resultant [`Location`].
     fn attempt_from(&self, location: Location) -> Location;
     /// The static array of all "forward" directions.
@@ -28,17 +30,105 @@ pub trait Shape: Sized + Copy + VariantArray + PartialEq + Eq + Hash + Ord + Par
     /// Convert the graph in `board` to an array representation.
     ///
     /// New shapes should implement this and determine a scheme by which the graph can be embedded in an [`ndarray::Array2`].
+    #[allow(private_interfaces)]
     fn gph_to_array(dims: (Dimension, Dimension), board: &UnGraphMap<Node<Self>, Edge<Self>>) -> Array2<FrozenCell<Self>>;
     /// Dump the specified [`ndarray::Array2`], laying out individual characters based on the geometry of the shape [`Self`].
     fn print(board: Array2<char>) -> String;
 }
 
+/// The direction in which the edge `e` leaves `this_node` for `neighbor`, whether it joins adjacent locations or is a warp.
+pub(crate) fn exit_direction<Sh: FullShape>(this_node: Node<Sh>, neighbor: Node<Sh>, e: &Edge<Sh>) -> Sh {
+    // not a warp if a "typical" step can reach the neighbor, direction_to would return Some
+    Sh::direction_to(this_node.location, neighbor.location).unwrap_or({
+        // warp; the direction in the edge struct is correct only if this node is indexed lower than its neighbor, otherwise it is reversed
+        let mut direction = e.direction;
+        if this_node < neighbor {
+            direction = direction.invert();
+        }
+
+        direction
+    })
+}
+
+/// Embed `board` in an array such that every node sits at the index given by its [`Location`].
+///
+/// This is suitable for any shape whose locations already form a dense rectangle of `dims`, which is the case for both built-in shapes.
+fn freeze_graph<Sh: FullShape>(dims: (Dimension, Dimension), board: &UnGraphMap<Node<Sh>, Edge<Sh>>) -> Array2<FrozenCell<Sh>> {
+    let mut ret: Array2<FrozenCell<Sh>> = Array2::from_shape_simple_fn((dims.1.get(), dims.0.get()), FrozenCell::default);
+
+    for (index, ptr) in ret.indexed_iter_mut() {
+        let relevant_nodes = board.nodes()
+            .filter(|n| n.location == Location::from(index))
+            .collect_vec();
+
+        ptr.assign_elem(match relevant_nodes.len() {
+            // this node was removed
+            0 => {
+                FrozenCell {
+                    exits: Default::default(),
+                    cell_type: FrozenCellType::Empty,
+                }
+            }
+            // this node exists and is not a bridge
+            1 => {
+                let mut exits = HashSet::with_capacity(Sh::VARIANTS.len());
+
+                let this_node = relevant_nodes.index(0);
+                for (n1, n2, e) in board.edges(*this_node) {
+                    let neighbor = if n1 == *this_node { n2 } else { n1 };
+                    exits.insert(exit_direction(*this_node, neighbor, e));
+                }
+
+                FrozenCell {
+                    exits,
+                    cell_type: match this_node.cell {
+                        Cell::Terminus { affiliation } => FrozenCellType::Terminus { affiliation: NonZero::new(affiliation).unwrap() },
+                        Cell::Path { affiliation } => FrozenCellType::Path { affiliation: NonZero::new(affiliation).unwrap() },
+                        Cell::Empty => FrozenCellType::Empty,
+                        _ => unreachable!()
+                    },
+                }
+            }
+            // this node is a bridge
+            _ => {
+                let mut exits = HashSet::with_capacity(Sh::VARIANTS.len());
+                let mut affiliations = HashMap::with_capacity(Sh::FORWARD_VARIANTS.len());
+
+                for node in relevant_nodes {
+                    match node.cell {
+                        Cell::Bridge { affiliation, direction } => {
+                            exits.insert(direction);
+                            exits.insert(direction.invert());
+                            affiliations.insert(
+                                direction.ensure_forward(),
+                                affiliation.and_then(NonZero::new),
+                            );
+                        }
+                        _ => unreachable!()
+                    }
+                }
+
+                FrozenCell {
+                    exits,
+                    cell_type: FrozenCellType::Bridge { affiliations },
+                }
+            }
+        })
+    }
+
+    ret
+}
+
 /// The square cell type and rectangular board shape, as found in Numberlink puzzles, Flow Free, and the Bridges and Warps expansions.
 #[derive(Copy, Clone, VariantArray, Eq, PartialEq, Hash, Debug, Ord, PartialOrd)]
 pub enum SquareStep {
+    /// Toward row 0.
     Up,
+    /// Away from row 0.
     Down,
+    /// Toward column 0.
     Left,
+    /// Away from column 0.
     Right,
     // switch it up like nintendo
 }
@@ -64,80 +154,9 @@ impl Shape for SquareStep {
         }
     }
 
+    #[allow(private_interfaces)]
     fn gph_to_array(dims: (Dimension, Dimension), board: &UnGraphMap<Node<Self>, Edge<Self>>) -> Array2<FrozenCell<Self>> {
-        let mut ret: Array2<FrozenCell<Self>> = Array2::from_shape_simple_fn((dims.1.get(), dims.0.get()), FrozenCell::default);
-
-        for (index, ptr) in ret.indexed_iter_mut() {
-            let relevant_nodes = board.nodes()
-                .filter(|n| n.location == Location::from(index))
-                .collect_vec();
-
-            ptr.assign_elem(match relevant_nodes.len() {
-                // this node was removed
-                0 => {
-                    FrozenCell {
-                        exits: Default::default(),
-                        cell_type: FrozenCellType::Empty,
-                    }
-                }
-                // this node exists and is not a bridge
-                1 => {
-                    let mut exits = HashSet::with_capacity(Self::VARIANTS.len());
-
-                    let this_node = relevant_nodes.index(0);
-                    for edge_triple in board.edges(*this_node) {
-                        let (n1, n2, e) = edge_triple;
-                        let neighbor = if n1 == *this_node { n2 } else { n1 };
-                        // not a warp if a "typical" step can reach the neighbor, direction_to would return Some
-                        exits.insert(Self::direction_to(this_node.location, neighbor.location).unwrap_or({
-                            // warp; the direction in the edge struct is correct only if this node is indexed lower than its neighbor, otherwise it is reversed
-                            let mut direction = e.direction;
-                            if *this_node < neighbor {
-                                direction = direction.invert();
-                            }
-
-                            direction
-                        }));
-                    }
-
-                    FrozenCell {
-                        exits,
-                        cell_type: match this_node.cell {
-                            Cell::Terminus { affiliation } => FrozenCellType::Terminus { affiliation: NonZero::new(affiliation).unwrap() },
-                            Cell::Path { affiliation } => FrozenCellType::Path { affiliation: NonZero::new(affiliation).unwrap() },
-                            Cell::Empty => FrozenCellType::Empty,
-                            _ => unreachable!()
-                        },
-                    }
-                }
-                    // this node is a bridge
-                _ => {
-                    let mut exits = HashSet::with_capacity(Self::VARIANTS.len());
-                    let mut affiliations = HashMap::with_capacity(Self::FORWARD_VARIANTS.len());
-
-                    for node in relevant_nodes {
-                        match node.cell {
-                            Cell::Bridge { affiliation, direction } => {
-                                exits.insert(direction);
-                                exits.insert(direction.invert());
-                                affiliations.insert(
-                                    direction.ensure_forward(),
-                                    affiliation.and_then(|aff| NonZero::new(aff)),
-                                );
-                            }
-                            _ => unreachable!()
-                        }
-                    }
-
-                    FrozenCell {
-                        exits,
-                        cell_type: FrozenCellType::Bridge { affiliations },
-                    }
-                }
-            })
-        }
-
-        ret
+        freeze_graph(dims, board)
     }
 
     fn print(board: Array2<char>) -> String {
@@ -154,31 +173,43 @@ impl Shape for SquareStep {
     }
 }
 
-// NB: we organize hexagonal grids as follows:
-// 0   1   2   3
-//   0   1   2   3
-// 0   1   2   3
-//   0   1   2   3
+/// The hexagonal cell type, as found in the Flow Free Hexes expansion.
+///
+/// Hexagons are flat-topped and sit in columns; every other row of the cell array is offset by half a cell to the right:
+/// ```text
+/// 0   1   2   3
+///   0   1   2   3
+/// 0   1   2   3
+///   0   1   2   3
+/// ```
+/// Hence, the cells directly above and below a cell are two rows away, and its four diagonal neighbors are in the adjacent rows.
 #[derive(Copy, Clone, VariantArray, Eq, PartialEq, Hash, Debug, Ord, PartialOrd)]
-enum HexStep {
+pub enum HexStep {
+    /// Two rows up, same column.
     Up,
+    /// One row up, half a cell to the right.
     UpRight,
+    /// One row down, half a cell to the right.
     RightDown,
+    /// Two rows down, same column.
     Down,
+    /// One row down, half a cell to the left.
     DownLeft,
+    /// One row up, half a cell to the left.
     LeftUp,
 }
 
 impl Shape for HexStep {
     fn attempt_from(&self, location: Location) -> Location {
+        // odd rows are shifted right, so stepping right from one only moves a column when we leave an odd row, and vice versa
+        let odd_row = !location.1.is_multiple_of(2);
         match self {
             Self::Up => location.offset_by((0, -2)),
-            // these are more complicated; consider the parity of the rows
-            Self::UpRight => location.offset_by((if location.1 % 2 == 0 { 1 } else { 0 }, -1)),
-            Self::RightDown => location.offset_by((if location.1 % 2 == 0 { 1 } else { 0 }, -1)),
+            Self::UpRight => location.offset_by((if odd_row { 1 } else { 0 }, -1)),
+            Self::RightDown => location.offset_by((if odd_row { 1 } else { 0 }, 1)),
             Self::Down => location.offset_by((0, 2)),
-            Self::DownLeft => location.offset_by((if location.1 % 2 == 0 { 0 } else { -1 }, 1)),
-            Self::LeftUp => location.offset_by((if location.1 % 2 == 0 { 0 } else { -1 }, -1)),
+            Self::DownLeft => location.offset_by((if odd_row { 0 } else { -1 }, 1)),
+            Self::LeftUp => location.offset_by((if odd_row { 0 } else { -1 }, -1)),
         }
     }
 
@@ -195,12 +226,24 @@ impl Shape for HexStep {
         }
     }
 
+    #[allow(private_interfaces)]
     fn gph_to_array(dims: (Dimension, Dimension), board: &UnGraphMap<Node<Self>, Edge<Self>>) -> Array2<FrozenCell<Self>> {
-        todo!()
+        freeze_graph(dims, board)
     }
 
     fn print(board: Array2<char>) -> String {
-        todo!()
+        // each cell takes 4 columns, and odd rows are indented by 2 more
+        let mut out = String::with_capacity(board.nrows() * (4 * board.ncols() + 1));
+
+        for (y, row) in board.rows().into_iter().enumerate() {
+            if !y.is_multiple_of(2) {
+                out.push_str("  ");
+            }
+            out.push_str(&row.iter().join("   "));
+            out.push('\n');
+        }
+
+        out
     }
 }
 
@@ -230,7 +273,7 @@ where
     }
 
     fn direction_to(a: Location, b: Location) -> Option<Self> {
-        Self::VARIANTS.iter().find(|dir| dir.attempt_from(a) == b).and_then(|dir| Some(*dir))
+        Self::VARIANTS.iter().find(|dir| dir.attempt_from(a) == b).copied()
     }
 
     fn ensure_forward(&self) -> Self {
@@ -239,4 +282,28 @@ where
             false => self.invert(),
         }
     }
-}
\ No newline at end of file
+}
+
+/// [`Send`] with the `parallel` feature, which solves on several threads, and implemented for every type without it.
+#[cfg(feature = "parallel")]
+pub trait MaybeSend: Send {}
+#[cfg(feature = "parallel")]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+/// [`Send`] with the `parallel` feature, which solves on several threads, and implemented for every type without it.
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSend {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// [`Sync`] with the `parallel` feature, which solves on several threads, and implemented for every type without it.
+#[cfg(feature = "parallel")]
+pub trait MaybeSync: Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Sync + ?Sized> MaybeSync for T {}
+
+/// [`Sync`] with the `parallel` feature, which solves on several threads, and implemented for every type without it.
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSync for T {}
@@ -1,54 +1,84 @@
-use std::collections::HashMap;
-use std::convert::identity;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
 use std::hash::Hash;
-use std::num::NonZero;
+use std::io;
+use std::io::{BufRead, BufWriter, Write};
 use std::ops::RangeInclusive;
+#[cfg(feature = "parallel")]
+use std::sync::mpsc;
+#[cfg(feature = "parallel")]
+use std::thread;
 
 use itertools::Itertools;
-use petgraph::graphmap::{NodeTrait, UnGraphMap};
+use petgraph::graphmap::UnGraphMap;
 use unordered_pair::UnorderedPair;
 use varisat::{CnfFormula, Lit, Solver, Var};
 
 use crate::affiliation::AffiliationID;
-use crate::logic::exactly_one;
+use crate::graph::{squares, Condition, Drawn, Guards, HasAffiliation, Solution, SolverFailure, Terminus, Uniqueness};
+use crate::backend;
+use crate::backend::{read_model, Backend};
+use crate::builder::SelfTouch;
+use crate::logic::{binary_exactly_one, cardinality, exactly_one, fresh_var, unary_count};
+#[cfg(feature = "parallel")]
+use crate::options::CancelToken;
+use crate::options::{AffiliationEncoding, Objective, SolveOptions};
+use crate::propagation::Forced;
 
-/// Constraint on node types given to [`GraphSolver`].
-pub trait Terminus: NodeTrait /* constraints on GraphMap */ {
-    fn is_terminus(&self) -> Option<NonZero<AffiliationID>>;
+/// Constraint on edge types given to [`GraphSolver::minimize`], which must know where a path bends.
+pub trait Straight {
+    /// Whether a path entering a vertex along this edge and leaving along `other` passes straight through it.
+    fn straight(&self, other: &Self) -> bool;
 }
 
-/// Reasons a [`GraphSolver`] may fail.
-#[derive(Debug)]
-pub enum SolverFailure {
-    /// The SAT solver detected a logical inconsistency, i.e. the graph as stated is unsolvable.
-    Inconsistent,
-    /// The SAT solver could not solve the affiliation of at least one node and/or edge.
-    /// This should probably never happen.
-    NoAffFound,
-}
+/// Vertices and edges which have the same affiliation in every solution of a graph, along with that affiliation, as found by [`GraphSolver::backbone`].
+pub(crate) type Backbone<N, E> = Vec<(HasAffiliation<N, E>, AffiliationID)>;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
-pub(crate) enum HasAffiliation<N, E>
-where
-    N: Terminus,
-{
-    Node { node: N },
-    Edge { edge: E, endpoints: UnorderedPair<N> },
+/// Add `lits` to every clause in `clauses`, so that none of them need hold when any of `lits` does.
+fn guard(clauses: Vec<Vec<Lit>>, lits: &[Lit]) -> Vec<Vec<Lit>> {
+    clauses.into_iter()
+        .map(|mut clause| {
+            clause.extend_from_slice(lits);
+            clause
+        })
+        .collect_vec()
 }
 
-impl<N, E> HasAffiliation<N, E>
-where
-    N: Terminus,
-    E: Copy,
-{
-    pub(crate) fn from_node(node: N) -> Self {
-        Self::Node { node }
-    }
+/// Run `solve` with the options of each racer in the [`SolveOptions::portfolio`] on a thread of its own, returning the first answer and cancelling the rest.
+///
+/// An answer is a solution or [`SolverFailure::Inconsistent`]; should no racer find one, the failure of the last to stop is returned.
+#[cfg(feature = "parallel")]
+pub(crate) fn race<T: Send>(options: &SolveOptions, solve: impl Fn(SolveOptions) -> Result<T, SolverFailure> + Sync) -> Result<T, SolverFailure> {
+    let limits = backend::watched();
+    let beaten = CancelToken::new();
+    let (sender, receiver) = mpsc::channel();
 
-    pub(crate) fn from_edge(triple: (N, N, &E)) -> Self {
-        let (n1, n2, e) = triple;
-        Self::Edge { edge: *e, endpoints: UnorderedPair(n1, n2) }
-    }
+    thread::scope(|scope| {
+        for racer in options.racers() {
+            // the limits of the whole solve still hold on every thread
+            let racer = SolveOptions { cancel: Some(beaten.clone()), ..racer };
+            let (sender, limits, beaten, solve) = (sender.clone(), limits.clone(), &beaten, &solve);
+            scope.spawn(move || {
+                let _limits = backend::adopt_within(limits, beaten);
+                // only fails once an answer is in, when nobody is listening
+                let _ = sender.send(solve(racer));
+            });
+        }
+        drop(sender);
+
+        let mut failure = None;
+        for result in receiver {
+            match result {
+                Ok(_) | Err(SolverFailure::Inconsistent) => {
+                    beaten.cancel();
+                    return result;
+                }
+                Err(reason) => failure = Some(reason),
+            }
+        }
+
+        Err(failure.unwrap())
+    })
 }
 
 /// The most general implementation of the logic necessary to solve a graph in accordance with the rules for Numberlink.
@@ -60,37 +90,121 @@ where
     N: Terminus,
 {
     graph: &'gph UnGraphMap<N, E>,
+    guards: Guards<N, E>,
     affiliation_holders: Vec<HasAffiliation<N, E>>,
+    // position of each holder in `affiliation_holders`, which determines its variables
+    holder_indices: HashMap<HasAffiliation<N, E>, usize>,
+    incident_edges: HashMap<N, Vec<HasAffiliation<N, E>>>,
     max_affiliation: AffiliationID,
+    options: SolveOptions,
+    forced: Vec<Forced<N, E>>,
+    self_touch: SelfTouch,
 }
 
 impl<'gph, N, E> From<&'gph UnGraphMap<N, E>> for GraphSolver<'gph, N, E>
 where
     N: Terminus,
-    E: Copy,
+    E: Eq + Hash + Copy,
 {
     fn from(graph: &'gph UnGraphMap<N, E>) -> Self {
         let mut affiliation_holders = Vec::with_capacity(graph.node_count() + graph.edge_count());
         let nodes = graph.nodes().collect_vec();
-        let num_affiliations = match nodes.iter().filter_map(|node| node.is_terminus().and_then(Some)).max() {
+        let num_affiliations = match nodes.iter().filter_map(|node| node.is_terminus()).max() {
             None => 0,
             Some(max) => max.get(),
         };
+        let incident_edges = nodes.iter()
+            .map(|node| (*node, graph.edges(*node).map(HasAffiliation::from_edge).collect_vec()))
+            .collect();
         affiliation_holders.extend(nodes.into_iter().map(HasAffiliation::from_node));
         affiliation_holders.extend(graph.all_edges().map(HasAffiliation::from_edge));
+        let holder_indices = affiliation_holders.iter()
+            .enumerate()
+            .map(|(index, holder)| (*holder, index))
+            .collect();
 
         Self {
             graph,
+            guards: Default::default(),
             affiliation_holders,
+            holder_indices,
+            incident_edges,
             max_affiliation: num_affiliations,
+            options: Default::default(),
+            forced: Vec::new(),
+            self_touch: Default::default(),
         }
     }
 }
 
-impl<N, E> GraphSolver<'_, N, E>
+impl<'gph, N, E> GraphSolver<'gph, N, E>
+where
+    N: Terminus,
+    E: Eq + Hash + Copy,
+{
+    /// Create a solver for `graph` whose parts are subject to `guards`.
+    pub(crate) fn with_guards(graph: &'gph UnGraphMap<N, E>, guards: Guards<N, E>) -> Self {
+        Self {
+            guards,
+            ..Self::from(graph)
+        }
+    }
+
+    /// Encode this graph as `options` says, rather than by default.
+    pub(crate) fn with_options(mut self, options: SolveOptions) -> Self {
+        if options.seed != 0 {
+            self.shuffle(options.seed);
+        }
+
+        Self {
+            options,
+            ..self
+        }
+    }
+
+    /// Reorder [`Self::affiliation_holders`], and so their variables, by a generator seeded with `seed`.
+    fn shuffle(&mut self, seed: u64) {
+        // splitmix64, which is plenty to vary the order without a dependency
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+
+        for index in (1..self.affiliation_holders.len()).rev() {
+            let other = (next() % (index as u64 + 1)) as usize;
+            self.affiliation_holders.swap(index, other);
+        }
+        self.holder_indices = self.affiliation_holders.iter()
+            .enumerate()
+            .map(|(index, holder)| (*holder, index))
+            .collect();
+    }
+
+    /// Forbid paths from touching themselves as `self_touch` says, rather than in every way.
+    pub(crate) fn with_self_touch(self, self_touch: SelfTouch) -> Self {
+        Self {
+            self_touch,
+            ..self
+        }
+    }
+
+    /// Assume every fact in `forced`, as found by [`propagate`](crate::propagation::propagate) on this graph.
+    pub(crate) fn with_forced(self, forced: Vec<Forced<N, E>>) -> Self {
+        Self {
+            forced,
+            ..self
+        }
+    }
+}
+
+impl<'gph, N, E> GraphSolver<'gph, N, E>
 where
     N: Terminus,
-    E: PartialEq + Eq + Hash + Copy,
+    E: PartialEq + Eq + Hash + Copy + Drawn,
 {
     #[inline]
     fn valid_affiliations(&self) -> RangeInclusive<AffiliationID> {
@@ -109,12 +223,11 @@ where
 
     #[inline]
     fn affiliation_var(&self, subject: HasAffiliation<N, E>, affiliation: AffiliationID) -> Var {
-        Var::from_index(self.affiliation_holders.iter().find_position(|elem| **elem == subject).unwrap().0
-            * self.num_affiliations() + affiliation)
+        Var::from_index(self.holder_indices[&subject] * self.num_affiliations() + affiliation)
     }
 
     #[inline]
-    fn solved_affiliation_of(&self, model: &Vec<Lit>, subject: HasAffiliation<N, E>, nonzero: bool) -> Option<AffiliationID> {
+    fn solved_affiliation_of(&self, model: &[Lit], subject: HasAffiliation<N, E>, nonzero: bool) -> Option<AffiliationID> {
         (if nonzero { self.valid_affiliations() } else { self.valid_non_null_affiliations() })
             .find(|aff| model.get(self.affiliation_var(subject, *aff).index()).unwrap().is_positive())
     }
@@ -140,94 +253,335 @@ where
     /// The two endpoints of E have the same affiliation if and only if E has the same nonzero affiliation.
     /// So, by complement, the two endpoints of E have different affiliation if and only if E has no affiliation.
     /// We encode the former of these two biconditionals.
-    pub fn solve(&self) -> Result<HashMap<HasAffiliation<N, E>, AffiliationID>, SolverFailure> {
+    ///
+    /// ## Connectivity
+    /// The above admits closed loops of some affiliation which are detached from both termini of that affiliation.
+    /// These are not encoded up front; instead, whenever a model contains such a loop, we forbid it and solve again.
+    ///
+    /// ## Encodings
+    /// "Exactly one" is stated as "at least one" and "at most one", the latter encoded as [`SolveOptions::at_most_one`] says.
+    /// With [`AffiliationEncoding::Binary`], the variable for each affiliation of a vertex or edge is instead defined from a binary number, so that exactly one holds without any further clauses.
+    ///
+    /// The clauses are solved by `backend`, which should not yet have any clauses.
+    pub fn solve(&self, mut backend: impl Backend) -> Result<Solution<N, E>, SolverFailure> {
+        let _limits = backend::watch(&self.options);
+        self.load(&mut backend);
+        self.next_solution(&mut backend)?.ok_or(SolverFailure::Inconsistent)
+    }
+
+    /// Read a model of the formula from [`Self::write_dimacs`], as written by an external SAT solver, and decode it.
+    ///
+    /// Both the output format of the SAT competitions and a bare list of literals are understood.
+    /// Returns [`SolverFailure::Inconsistent`] if the solver found no model, or [`SolverFailure::Backend`] if the model cannot be read, does not satisfy every clause, or contains a detached cycle.
+    pub fn read_model(&self, input: impl BufRead) -> Result<Solution<N, E>, SolverFailure> {
+        let (formulae, mut assumptions, _) = self.encode();
+        assumptions.extend(self.selector_assumptions(|_| true));
+
+        let model = match read_model(input, self.aux_var_base()).map_err(SolverFailure::Backend)? {
+            None => return Err(SolverFailure::Backend("no answer".to_string())),
+            Some(None) => return Err(SolverFailure::Inconsistent),
+            Some(Some(model)) => model,
+        };
+
+        let holds = |lit: &Lit| model.get(lit.index()).copied().unwrap_or(lit.var().negative()) == *lit;
+        if !assumptions.iter().all(holds) || !formulae.iter().flat_map(|formula| formula.iter()).all(|clause| clause.iter().any(holds)) {
+            return Err(SolverFailure::Backend("model does not satisfy every clause".to_string()));
+        }
+
+        let solution = self.decode(&model)?;
+        if !self.detached_cycles(&solution).is_empty() {
+            return Err(SolverFailure::Backend("model contains a detached cycle".to_string()));
+        }
+
+        Ok(solution)
+    }
+
+    /// Determine whether this graph has exactly one solution, returning up to two distinct solutions as witnesses.
+    ///
+    /// Two solutions are distinct if any edge has a different affiliation between them.
+    /// Vertex affiliations follow from edge affiliations, so they need not be considered.
+    pub fn check_unique(&self) -> Result<Uniqueness<Solution<N, E>>, SolverFailure> {
+        let mut solver = Solver::new();
+        self.load(&mut solver);
+
+        let first = match self.next_solution(&mut solver)? {
+            None => return Ok(Uniqueness::Unsolvable),
+            Some(solution) => solution,
+        };
+
+        // forbid this exact set of edge affiliations, then try again
+        solver.add_clause(&self.blocking_clause(&first));
+
+        Ok(match self.next_solution(&mut solver)? {
+            None => Uniqueness::Unique(first),
+            Some(second) => Uniqueness::Multiple(first, second),
+        })
+    }
+
+    /// Find a set among the `soft` selectors whose states in [`Guards`] cannot all hold in any solution, returning [`None`] if the graph is solvable.
+    ///
+    /// Other selectors are held in their given states, while `soft` selectors outside the set are put in the opposite state.
+    /// The set is minimal: putting any one of its selectors in the opposite state as well makes the graph solvable.
+    /// Selectors with higher indices are left out first where possible, so lower ones are preferred in the result.
+    pub(crate) fn conflicting_selectors(&self, soft: &[usize], mut solver: impl Backend) -> Result<Option<Vec<usize>>, SolverFailure> {
+        let (fixed_assumptions, _) = self.load(&mut solver);
+        if self.next_solution(&mut solver)?.is_some() {
+            return Ok(None);
+        }
+
+        let mut conflict = self.failed_selectors(&solver, |selector| soft.contains(&selector));
+
+        // the core reported by the solver need not be minimal, so try leaving out each selector in turn until none can be
+        loop {
+            let mut shrunk = false;
+
+            for selector in conflict.clone().into_iter().rev() {
+                if !conflict.contains(&selector) {
+                    continue;
+                }
+
+                let candidate = conflict.iter().copied().filter(|other| *other != selector).collect_vec();
+                let mut assumptions = fixed_assumptions.clone();
+                assumptions.extend(self.selector_assumptions(|other| !soft.contains(&other) || candidate.contains(&other)));
+                solver.assume(&assumptions);
+
+                if self.next_solution(&mut solver)?.is_none() {
+                    // this selector is not needed, and possibly neither are some others
+                    conflict = self.failed_selectors(&solver, |other| candidate.contains(&other));
+                    shrunk = true;
+                }
+            }
+
+            // leaving out one selector may have made another one unnecessary, so go around again until nothing changes
+            if !shrunk {
+                return Ok(Some(conflict));
+            }
+        }
+    }
+
+    /// Find which of the `candidates` have the same affiliation in every solution, returning [`None`] if there is no solution.
+    ///
+    /// `candidates` picks the vertices and edges to check from a first solution, in the order they should be checked; each is paired with its affiliation there.
+    /// Each candidate is checked by assuming it has some other affiliation; if no solution remains, it is forced, and otherwise every candidate the new solution disagrees with is ruled out as well.
+    /// Checking stops once `limit` forced candidates are found, if it is given.
+    pub(crate) fn backbone(
+        &self,
+        mut solver: impl Backend,
+        candidates: impl FnOnce(&Solution<N, E>) -> Vec<HasAffiliation<N, E>>,
+        limit: Option<usize>,
+    ) -> Result<Option<Backbone<N, E>>, SolverFailure> {
+        let (fixed_assumptions, _) = self.load(&mut solver);
+        let Some(first) = self.next_solution(&mut solver)? else {
+            return Ok(None);
+        };
+
+        let mut remaining: VecDeque<(HasAffiliation<N, E>, AffiliationID)> = candidates(&first).into_iter()
+            .map(|subject| (subject, first[&subject]))
+            .collect();
+        let mut forced = Vec::new();
+
+        while let Some((subject, aff)) = remaining.pop_front() {
+            if limit.is_some_and(|limit| forced.len() >= limit) {
+                break;
+            }
+
+            let mut assumptions = fixed_assumptions.clone();
+            assumptions.extend(self.selector_assumptions(|_| true));
+            assumptions.push(self.affiliation_var(subject, aff).negative());
+            solver.assume(&assumptions);
+
+            match self.next_solution(&mut solver)? {
+                None => forced.push((subject, aff)),
+                // anything this solution disagrees with is not forced either
+                Some(other) => remaining.retain(|(subject, aff)| other[subject] == *aff),
+            }
+        }
+
+        Ok(Some(forced))
+    }
+
+    /// Read the selectors assumed to be in their given state in the failed core of `solver`, keeping only those for which `include` holds.
+    fn failed_selectors(&self, solver: &impl Backend, include: impl Fn(usize) -> bool) -> Vec<usize> {
+        let failed = solver.failed_core().unwrap_or_default();
+
+        (0..self.guards.selectors.len())
+            .filter(|selector| include(*selector))
+            .filter(|selector| failed.contains(&self.selector_var(*selector).lit(self.guards.selectors[*selector])))
+            .collect_vec()
+    }
+
+    /// Lazily enumerate the solutions of this graph, stopping after `cap` solutions if it is given.
+    ///
+    /// Each solution differs from every other in the affiliation of at least one edge; see [`Self::check_unique`].
+    /// The iterator ends once no further solution exists, or after yielding the first [`Err`];
+    /// a backend stopped by [`SolveOptions::deadline`] or the like may be unusable, so enumerate again from a fresh one rather than resume.
+    pub fn solutions(self, cap: Option<usize>) -> Solutions<'gph, N, E> {
+        self.solutions_with(cap, Solver::new())
+    }
+
+    /// Enumerate solutions as in [`Self::solutions`], but using `backend`, which should not yet have any clauses.
+    pub fn solutions_with<B: Backend>(self, cap: Option<usize>, backend: B) -> Solutions<'gph, N, E, B> {
+        let mut solver = backend;
+        self.load(&mut solver);
+
+        Solutions {
+            graph_solver: self,
+            solver,
+            remaining: cap,
+            exhausted: false,
+        }
+    }
+
+    /// Generate the clause stating that at least one edge has an affiliation other than the one it has in `solution`.
+    fn blocking_clause(&self, solution: &Solution<N, E>) -> Vec<Lit> {
+        self.graph.all_edges()
+            .map(|edge_triple| {
+                let subject = HasAffiliation::from_edge(edge_triple);
+                self.affiliation_var(subject, *solution.get(&subject).unwrap()).negative()
+            })
+            .collect_vec()
+    }
+
+    /// Load `solver` with the clauses describing this graph, assuming every selector in [`Guards`] is in its given state.
+    /// See [`Self::solve`] for the logical setup.
+    ///
+    /// Returns the assumptions which hold regardless of the selectors, to be passed to [`Self::assume_selectors`] should they change,
+    /// and the first variable the clauses leave unused, from which any clauses added later may take auxiliary variables.
+    pub(crate) fn load(&self, solver: &mut impl Backend) -> (Vec<Lit>, usize) {
+        let (formulae, fixed_assumptions, next_aux_var) = self.encode();
+
+        if self.options.limited() {
+            solver.interruptible();
+        }
+        formulae.iter().for_each(|formula| solver.add_formula(formula));
+        self.assume_selectors(solver, &fixed_assumptions);
+
+        (fixed_assumptions, next_aux_var)
+    }
+
+    /// Replace the assumptions of `solver`, already loaded by [`Self::load`] for a graph with the same superset, so that every selector is in its state in [`Guards`].
+    ///
+    /// Nothing is encoded again, so features may be switched on and off cheaply between calls to [`Self::next_solution`].
+    pub(crate) fn assume_selectors(&self, solver: &mut impl Backend, fixed_assumptions: &[Lit]) {
+        let mut assumptions = fixed_assumptions.to_vec();
+        assumptions.extend(self.selector_assumptions(|_| true));
+        solver.assume(&assumptions);
+    }
+
+    /// Generate the clauses describing this graph, along with the assumptions which must always hold regardless of the state of any selector,
+    /// and the first variable they leave unused, from which any further auxiliary variables may be taken.
+    fn encode(&self) -> (Vec<CnfFormula>, Vec<Lit>, usize) {
         let mut assumptions: Vec<Lit> = Vec::new();
         let mut formulae: Vec<CnfFormula> = Vec::new();
+        let mut next_aux_var = self.aux_var_base();
+
+        // facts found by propagation hold in every solution, so the solver may as well start from them
+        assumptions.extend(self.forced.iter().map(|forced| match *forced {
+            Forced::Used(_, edge) => self.affiliation_var(edge, 0).negative(),
+            Forced::Unused(_, edge) => self.affiliation_var(edge, 0).positive(),
+            Forced::Affiliated(_, node, aff) => self.affiliation_var(HasAffiliation::from_node(node), aff).positive(),
+        }));
 
         for vertex in self.graph.nodes() {
             // let this vertex be V
-            if let Some(aff) = vertex.is_terminus() {
+            let subject = HasAffiliation::from_node(vertex);
+            let null = self.affiliation_var(subject, 0);
+            let present = self.condition_lit(self.guards.nodes.get(&vertex), &mut next_aux_var, &mut formulae);
+            let filled = self.condition_lit(self.guards.filled.get(&vertex), &mut next_aux_var, &mut formulae);
+            let active = match vertex.is_terminus() {
+                None => None,
+                Some(_) => self.condition_lit(self.guards.termini.get(&vertex), &mut next_aux_var, &mut formulae),
+            };
+
+            // while each restriction on V holds, V does not have an affiliation forbidden to it and has one required of it
+            let restrictions = self.guards.forbidden.get(&vertex).into_iter().flatten().map(|restriction| (restriction, false))
+                .chain(self.guards.required.get(&vertex).into_iter().flatten().map(|restriction| (restriction, true)));
+            for ((aff, condition), required) in restrictions {
+                let mut clause = self.condition_lit(Some(condition), &mut next_aux_var, &mut formulae).map(|holds| !holds).into_iter().collect_vec();
+                // an affiliation without termini on this graph is never taken
+                if self.valid_non_null_affiliations().contains(aff) {
+                    clause.push(self.affiliation_var(subject, *aff).lit(required));
+                } else if !required {
+                    continue;
+                }
+                formulae.push(CnfFormula::from(vec![clause]));
+            }
+
+            if let (Some(aff), None, None) = (vertex.is_terminus(), present, active) {
                 // the affiliation of V is the one already assigned, and no other; we tell the solver to assume this is so
                 assumptions.extend(self.valid_affiliations()
-                    .map(|maybe_aff| self.affiliation_var(HasAffiliation::from_node(vertex), maybe_aff).lit(maybe_aff == aff.get())));
+                    .map(|maybe_aff| self.affiliation_var(subject, maybe_aff).lit(maybe_aff == aff.get())));
 
-                // exactly one incident edge E has the same affiliation
-                formulae.push(CnfFormula::from(exactly_one(
-                    self.graph.edges(vertex)
-                        .map(|e_triple| self.affiliation_var(HasAffiliation::from_edge(e_triple), aff.get()).positive())
-                        .collect_vec()
-                )));
+                formulae.push(CnfFormula::from(self.terminus_clauses(vertex, aff.get(), &mut next_aux_var)));
+                continue;
+            }
 
-                // V has deg(V) - 1 incident edges with affiliation 0 (unaffiliated)
-                // or, equivalently, exactly 1 incident edge does *not* have affiliation 0
-                formulae.push(CnfFormula::from(exactly_one(
-                    self.graph.edges(vertex)
-                        .map(|e_triple| self.affiliation_var(HasAffiliation::from_edge(e_triple), 0).negative())
-                        .collect_vec()
-                )));
-            } else {
-                // V must have nonzero affiliation
-                assumptions.push(self.affiliation_var(HasAffiliation::from_node(vertex), 0).negative());
+            if let Some(aff) = vertex.drawn() {
+                self.drawn_clauses(subject, aff.get(), &mut next_aux_var, &mut formulae, &mut assumptions);
+            }
+
+            if let Some(present) = present {
+                // V is unaffiliated while it is absent
+                formulae.push(CnfFormula::from(vec![vec![present, null.positive()]]));
+            }
+
+            // V has nonzero affiliation while it is present and must be filled
+            let unfilled = [present, filled].into_iter().flatten().map(|lit| !lit).collect_vec();
+            if unfilled.is_empty() {
+                assumptions.push(null.negative());
 
                 // V has only one affiliation
-                formulae.push(CnfFormula::from(exactly_one(
+                formulae.push(CnfFormula::from(self.one_affiliation(
                     self.valid_non_null_affiliations()
-                        .map(|aff| self.affiliation_var(HasAffiliation::from_node(vertex), aff).positive())
-                        .collect_vec()
+                        .map(|aff| self.affiliation_var(subject, aff).positive())
+                        .collect_vec(),
+                    &mut next_aux_var,
                 )));
+            } else {
+                formulae.push(CnfFormula::from(vec![unfilled.into_iter().chain([null.negative()]).collect_vec()]));
 
-                let all_incident = self.graph.edges(vertex).collect_vec();
-
-                for aff in self.valid_non_null_affiliations() {
-                    {
-                        let mut terms = Vec::with_capacity(1 + all_incident.len());
-                        // V having affiliation A...
-                        terms.push(self.affiliation_var(HasAffiliation::from_node(vertex), aff).negative());
-
-                        // implies at least one incident edge E_1 has the same affiliation
-                        terms.extend(all_incident.iter()
-                            .map(|e_triple| self.affiliation_var(HasAffiliation::from_edge(*e_triple), aff).positive())
-                        );
-
-                        formulae.push(CnfFormula::from(vec![terms]))
-                    }
-                    {
-                        formulae.push(CnfFormula::from(all_incident.iter()
-                            .map(|e1_triple| {
-                                // some incident E_0 having affiliation A implies that another E incident to V has affiliation A
-                                // or, if we let X = (E_0 has affiliation A), Y = (E_1 has affiliation A), Z = (E_2 has affiliation A), and so on...
-                                // X => Y + Z + ...
-                                // = !X + Y + Z + ...
-                                // in other words, the variable is positive for all incident E unless E is E_1
-                                all_incident.iter()
-                                    .map(|e_triple| self.affiliation_var(HasAffiliation::from_edge(*e_triple), aff).lit(e1_triple != e_triple))
-                                    .collect_vec()
-                            })));
-                    }
-
-                    // however, no three such E exist; i.e. for any choice of 3 incident E (E_1, E_2, E_3), at least one does not have affiliation A
-                    let no_three_clauses = all_incident.iter()
-                        .combinations(3)
-                        // one choice for (E_1, E_2, E_3) as mentioned above
-                        .map(|selection| selection.iter()
-                            // for each of these three, generate the literal stating its affiliation is not A
-                            .map(|e_triple| self.affiliation_var(HasAffiliation::from_edge(**e_triple), aff).negative())
-                            .collect_vec()
-                        );
+                // V has only one affiliation, which may be 0
+                formulae.push(CnfFormula::from(self.one_affiliation(
+                    self.valid_affiliations()
+                        .map(|aff| self.affiliation_var(subject, aff).positive())
+                        .collect_vec(),
+                    &mut next_aux_var,
+                )));
+            }
 
-                    formulae.push(CnfFormula::from(no_three_clauses));
+            match vertex.is_terminus() {
+                Some(aff) => {
+                    // while V is present and an active terminus, V has its assigned affiliation and behaves as such
+                    let inactive = [present, active].into_iter().flatten().map(|lit| !lit).collect_vec();
+                    formulae.push(CnfFormula::from(vec![inactive.iter().copied().chain([self.affiliation_var(subject, aff.get()).positive()]).collect_vec()]));
+                    formulae.push(CnfFormula::from(guard(self.terminus_clauses(vertex, aff.get(), &mut next_aux_var), &inactive)));
+                    // otherwise, V is like any other vertex; an absent V is unaffiliated, so the path clauses hold trivially
+                    formulae.push(CnfFormula::from(guard(self.path_clauses(vertex), active.or(present).as_slice())));
                 }
+                None => formulae.push(CnfFormula::from(self.path_clauses(vertex))),
             }
         }
 
         for edge_triple in self.graph.all_edges() {
+            let usable = self.condition_lit(self.guards.edges.get(&HasAffiliation::from_edge(edge_triple)), &mut next_aux_var, &mut formulae);
+
             // this edge E has exactly one affiliation, which may be 0
-            formulae.push(CnfFormula::from(exactly_one(
+            formulae.push(CnfFormula::from(self.one_affiliation(
                 self.valid_affiliations()
                     .map(|aff| self.affiliation_var(HasAffiliation::from_edge(edge_triple), aff).positive())
-                    .collect_vec()
+                    .collect_vec(),
+                &mut next_aux_var,
             )));
 
+            if let Some(aff) = edge_triple.2.drawn() {
+                self.drawn_clauses(HasAffiliation::from_edge(edge_triple), aff.get(), &mut next_aux_var, &mut formulae, &mut assumptions);
+            }
+
+            if let Some(usable) = usable {
+                // E is unaffiliated while it cannot be used
+                formulae.push(CnfFormula::from(vec![vec![usable, self.affiliation_var(HasAffiliation::from_edge(edge_triple), 0).positive()]]));
+            }
+
             for aff in self.valid_non_null_affiliations() {
                 // E having a non-null affiliation <=> its vertices have the same affiliation
                 // let this be A <=> BC
@@ -238,28 +592,282 @@ where
                 let b = self.affiliation_var(HasAffiliation::from_node(edge_triple.0), aff);
                 let c = self.affiliation_var(HasAffiliation::from_node(edge_triple.1), aff);
 
+                let mut bc_implies_a = vec![a.positive(), b.negative(), c.negative()];
+                // if E cannot be used, BC does not force anything
+                bc_implies_a.extend(usable.map(|usable| !usable));
+
                 formulae.push(CnfFormula::from(vec![
                     vec![a.negative(), b.positive()],
                     vec![a.negative(), c.positive()],
-                    vec![a.positive(), b.negative(), c.negative()],
-                ]))
+                ]));
+                // a path may run alongside itself only if allowed to
+                if self.self_touch.orthogonal {
+                    formulae.push(CnfFormula::from(vec![bc_implies_a]));
+                }
             }
         }
 
-        let mut solver = Solver::new();
-        formulae.into_iter().for_each(|formula| solver.add_formula(&formula));
-        solver.assume(assumptions.into_iter().as_ref());
-        if !solver.solve().is_ok_and(identity) {
-            return Err(SolverFailure::Inconsistent);
+        for (aff, lengths) in &self.guards.lengths {
+            // an affiliation without termini on this graph has no path to measure
+            if !self.valid_non_null_affiliations().contains(aff) {
+                continue;
+            }
+
+            // a path passes through one more vertex than it has edges
+            let lits = self.graph.all_edges()
+                .map(|edge_triple| self.affiliation_var(HasAffiliation::from_edge(edge_triple), *aff).positive())
+                .collect_vec();
+            for (length, condition) in lengths {
+                let holds = self.condition_lit(Some(condition), &mut next_aux_var, &mut formulae);
+                let edges = length.start().saturating_sub(1)..=length.end().saturating_sub(1);
+                let clauses = cardinality(&lits, edges, &mut next_aux_var);
+                formulae.push(CnfFormula::from(guard(clauses, holds.map(|holds| !holds).as_slice())));
+            }
+        }
+
+        if !self.guards.directed.is_empty() || !self.guards.used.is_empty() {
+            self.orientation_clauses(&mut next_aux_var, &mut formulae);
+        }
+
+        // otherwise, a square of four vertices all of one affiliation would have to be a cycle
+        if self.self_touch.blocks && !self.self_touch.orthogonal {
+            for (square, aff) in squares(self.graph).into_iter().cartesian_product(self.valid_non_null_affiliations()) {
+                formulae.push(CnfFormula::from(vec![square.iter()
+                    .map(|vertex| self.affiliation_var(HasAffiliation::from_node(*vertex), aff).negative())
+                    .collect_vec()]));
+            }
+        }
+
+        (formulae, assumptions, next_aux_var)
+    }
+
+    /// Orient every path from one of its termini to the other, then push the clauses stating that each edge in [`Guards::directed`] is travelled only the way it allows,
+    /// and each in [`Guards::used`] is travelled at all.
+    fn orientation_clauses(&self, next_aux_var: &mut usize, formulae: &mut Vec<CnfFormula>) {
+        // the orientation of each edge E is true if E is travelled from the lesser of its endpoints to the greater
+        let orientations: HashMap<HasAffiliation<N, E>, Var> = self.graph.all_edges()
+            .map(|edge_triple| (HasAffiliation::from_edge(edge_triple), fresh_var(next_aux_var)))
+            .collect();
+        let entering = |edge: HasAffiliation<N, E>, vertex: N| {
+            let HasAffiliation::Edge { endpoints: UnorderedPair(n1, n2), .. } = edge else {
+                unreachable!()
+            };
+            orientations[&edge].lit(vertex == n1.max(n2))
         };
-        let model = solver.model().unwrap();
 
+        // where two incident edges of V are both used, the path enters V along one and leaves along the other
+        // a Terminus has only one, so the path leaves one Terminus and enters the other
+        for vertex in self.graph.nodes() {
+            for (e1, e2) in self.incident_edges[&vertex].iter().tuple_combinations() {
+                let (unused1, unused2) = (self.affiliation_var(*e1, 0).positive(), self.affiliation_var(*e2, 0).positive());
+                let (in1, in2) = (entering(*e1, vertex), entering(*e2, vertex));
+                formulae.push(CnfFormula::from(vec![
+                    vec![unused1, unused2, in1, in2],
+                    vec![unused1, unused2, !in1, !in2],
+                ]));
+            }
+        }
+
+        for (edge, directions) in &self.guards.directed {
+            for (from, condition) in directions {
+                let holds = self.condition_lit(Some(condition), next_aux_var, formulae);
+                // while the direction holds, E is unused or travelled away from its start
+                let clause = [self.affiliation_var(*edge, 0).positive(), !entering(*edge, *from)].into_iter()
+                    .chain(holds.map(|holds| !holds))
+                    .collect_vec();
+                formulae.push(CnfFormula::from(vec![clause]));
+            }
+        }
+
+        for (edge, conditions) in &self.guards.used {
+            for condition in conditions {
+                let holds = self.condition_lit(Some(condition), next_aux_var, formulae);
+                let clause = [self.affiliation_var(*edge, 0).negative()].into_iter()
+                    .chain(holds.map(|holds| !holds))
+                    .collect_vec();
+                formulae.push(CnfFormula::from(vec![clause]));
+            }
+        }
+    }
+
+    /// Generate the clauses stating that Terminus V with affiliation `aff` is the end of a path.
+    fn terminus_clauses(&self, vertex: N, aff: AffiliationID, next_aux_var: &mut usize) -> Vec<Vec<Lit>> {
+        let mut clauses = Vec::new();
+
+        // exactly one incident edge E has the same affiliation
+        clauses.extend(exactly_one(
+            self.incident_edges[&vertex].iter()
+                .map(|edge| self.affiliation_var(*edge, aff).positive())
+                .collect_vec(),
+            self.options.at_most_one,
+            next_aux_var,
+        ));
+
+        // V has deg(V) - 1 incident edges with affiliation 0 (unaffiliated)
+        // or, equivalently, exactly 1 incident edge does *not* have affiliation 0
+        clauses.extend(exactly_one(
+            self.incident_edges[&vertex].iter()
+                .map(|edge| self.affiliation_var(*edge, 0).negative())
+                .collect_vec(),
+            self.options.at_most_one,
+            next_aux_var,
+        ));
+
+        clauses
+    }
+
+    /// Generate the clauses stating that non-Terminus V, if affiliated, is in the middle of a path.
+    fn path_clauses(&self, vertex: N) -> Vec<Vec<Lit>> {
+        let mut clauses = Vec::new();
+        let all_incident = &self.incident_edges[&vertex];
+
+        for aff in self.valid_non_null_affiliations() {
+            {
+                let mut terms = Vec::with_capacity(1 + all_incident.len());
+                // V having affiliation A...
+                terms.push(self.affiliation_var(HasAffiliation::from_node(vertex), aff).negative());
+
+                // implies at least one incident edge E_1 has the same affiliation
+                terms.extend(all_incident.iter()
+                    .map(|edge| self.affiliation_var(*edge, aff).positive())
+                );
+
+                clauses.push(terms);
+            }
+            {
+                clauses.extend(all_incident.iter()
+                    .map(|e1| {
+                        // some incident E_0 having affiliation A implies that another E incident to V has affiliation A
+                        // or, if we let X = (E_0 has affiliation A), Y = (E_1 has affiliation A), Z = (E_2 has affiliation A), and so on...
+                        // X => Y + Z + ...
+                        // = !X + Y + Z + ...
+                        // in other words, the variable is positive for all incident E unless E is E_1
+                        all_incident.iter()
+                            .map(|edge| self.affiliation_var(*edge, aff).lit(e1 != edge))
+                            .collect_vec()
+                    }));
+            }
+
+            // however, no three such E exist; i.e. for any choice of 3 incident E (E_1, E_2, E_3), at least one does not have affiliation A
+            let no_three_clauses = all_incident.iter()
+                .combinations(3)
+                // one choice for (E_1, E_2, E_3) as mentioned above
+                .map(|selection| selection.iter()
+                    // for each of these three, generate the literal stating its affiliation is not A
+                    .map(|edge| self.affiliation_var(**edge, aff).negative())
+                    .collect_vec()
+                );
+
+            clauses.extend(no_three_clauses);
+        }
+
+        clauses
+    }
+
+    /// State that `subject`, drawn with affiliation `aff`, has that affiliation while its condition in [`Guards::drawn`] holds.
+    ///
+    /// Without a condition, this is an assumption, as for a Terminus.
+    fn drawn_clauses(&self, subject: HasAffiliation<N, E>, aff: AffiliationID, next_aux_var: &mut usize, formulae: &mut Vec<CnfFormula>, assumptions: &mut Vec<Lit>) {
+        let var = self.affiliation_var(subject, aff);
+        match self.condition_lit(self.guards.drawn.get(&subject), next_aux_var, formulae) {
+            None => assumptions.push(var.positive()),
+            Some(drawn) => formulae.push(CnfFormula::from(vec![vec![!drawn, var.positive()]])),
+        }
+    }
+
+    /// Generate the clauses stating that exactly one of `vars`, the variables for some affiliations of one vertex or edge, is true.
+    fn one_affiliation(&self, vars: Vec<Lit>, next_aux_var: &mut usize) -> Vec<Vec<Lit>> {
+        match self.options.affiliations {
+            AffiliationEncoding::OneHot => exactly_one(vars, self.options.at_most_one, next_aux_var),
+            AffiliationEncoding::Binary => binary_exactly_one(&vars, next_aux_var),
+        }
+    }
+
+    /// Get a literal which is true exactly when `condition` holds, or [`None`] if there is no condition.
+    ///
+    /// A condition on several selectors requires an auxiliary variable, which is defined by clauses pushed to `formulae`.
+    fn condition_lit(&self, condition: Option<&Condition>, next_aux_var: &mut usize, formulae: &mut Vec<CnfFormula>) -> Option<Lit> {
+        let lits = condition?.iter()
+            .map(|(selector, state)| self.selector_var(*selector).lit(*state))
+            .collect_vec();
+
+        match lits.len() {
+            0 => None,
+            1 => Some(lits[0]),
+            _ => {
+                let aux = fresh_var(next_aux_var);
+
+                // aux <=> l_1 * l_2 * ...
+                // aux => l_i for every i, and l_1 * l_2 * ... => aux = !l_1 + !l_2 + ... + aux
+                let mut clauses = lits.iter().map(|lit| vec![aux.negative(), *lit]).collect_vec();
+                clauses.push(lits.iter().map(|lit| !*lit).chain([aux.positive()]).collect_vec());
+                formulae.push(CnfFormula::from(clauses));
+
+                Some(aux.positive())
+            }
+        }
+    }
+
+    #[inline]
+    fn selector_var(&self, selector: usize) -> Var {
+        Var::from_index(self.affiliation_holders.len() * self.num_affiliations() + selector)
+    }
+
+    #[inline]
+    fn aux_var_base(&self) -> usize {
+        self.affiliation_holders.len() * self.num_affiliations() + self.guards.selectors.len()
+    }
+
+    /// Generate assumptions fixing each selector for which `include` holds to its state in [`Guards`], and every other selector to the opposite state.
+    fn selector_assumptions(&self, include: impl Fn(usize) -> bool) -> Vec<Lit> {
+        self.guards.selectors.iter()
+            .enumerate()
+            .map(|(selector, state)| self.selector_var(selector).lit(*state == include(selector)))
+            .collect_vec()
+    }
+
+    /// Run `solver` until it finds a model free of detached cycles, returning [`None`] if no such model remains or the solved affiliations otherwise.
+    ///
+    /// The clauses from [`Self::load`] only constrain each vertex and its incident edges, so a model may contain a closed loop of some affiliation which touches neither terminus of that affiliation.
+    /// Whenever this happens, we add a clause cutting every such loop and solve again.
+    pub(crate) fn next_solution(&self, solver: &mut impl Backend) -> Result<Option<Solution<N, E>>, SolverFailure> {
+        loop {
+            // a backend which cannot be interrupted is at least stopped between calls
+            backend::check_limits()?;
+            if !solver.solve()? {
+                return Ok(None);
+            };
+            let model = solver.model().unwrap();
+
+            let solved_affiliations = self.decode(&model)?;
+
+            let cycles = self.detached_cycles(&solved_affiliations);
+            if cycles.is_empty() {
+                return Ok(Some(solved_affiliations));
+            }
+
+            for cycle in cycles {
+                // no affiliation may be shared by every edge of this cycle
+                // this is sound since the edges of any one affiliation form a simple path in every true solution
+                for aff in self.valid_non_null_affiliations() {
+                    solver.add_clause(&cycle.iter()
+                        .map(|edge| self.affiliation_var(*edge, aff).negative())
+                        .collect_vec());
+                }
+            }
+        }
+    }
+
+    /// Read the affiliation of every vertex and edge from `model`.
+    fn decode(&self, model: &[Lit]) -> Result<Solution<N, E>, SolverFailure> {
         let mut solved_affiliations = HashMap::new();
 
         for node in self.graph.nodes() {
+            // only a guarded vertex may be left unaffiliated
+            let guarded = self.guards.nodes.contains_key(&node) || self.guards.filled.contains_key(&node);
             solved_affiliations.insert(
                 HasAffiliation::from_node(node),
-                match self.solved_affiliation_of(&model, HasAffiliation::from_node(node), false) {
+                match self.solved_affiliation_of(model, HasAffiliation::from_node(node), guarded) {
                     None => return Err(SolverFailure::NoAffFound),
                     Some(aff) => aff
                 });
@@ -268,7 +876,7 @@ where
         for edge_triple in self.graph.all_edges() {
             solved_affiliations.insert(
                 HasAffiliation::from_edge(edge_triple),
-                match self.solved_affiliation_of(&model, HasAffiliation::from_edge(edge_triple), true) {
+                match self.solved_affiliation_of(model, HasAffiliation::from_edge(edge_triple), true) {
                     None => return Err(SolverFailure::NoAffFound),
                     Some(aff) => aff
                 });
@@ -276,4 +884,230 @@ where
 
         Ok(solved_affiliations)
     }
+
+    /// Find every set of affiliated edges in `solution` which is connected and forms a cycle.
+    ///
+    /// Every vertex has at most two incident edges of its own affiliation, so each connected set is either a path or a cycle.
+    /// A path has one fewer edge than it has vertices, whereas a cycle has as many edges as vertices.
+    fn detached_cycles(&self, solution: &Solution<N, E>) -> Vec<Vec<HasAffiliation<N, E>>> {
+        // union-find over vertices, joined along affiliated edges
+        let index_of: HashMap<N, usize> = self.graph.nodes().enumerate().map(|(ind, node)| (node, ind)).collect();
+        let mut parents = (0..index_of.len()).collect_vec();
+
+        fn root(parents: &mut [usize], mut ind: usize) -> usize {
+            while parents[ind] != ind {
+                parents[ind] = parents[parents[ind]];
+                ind = parents[ind];
+            }
+            ind
+        }
+
+        let affiliated_edges = self.graph.all_edges()
+            .filter(|edge_triple| *solution.get(&HasAffiliation::from_edge(*edge_triple)).unwrap() != 0)
+            .collect_vec();
+
+        for (n1, n2, _) in affiliated_edges.iter() {
+            let (r1, r2) = (root(&mut parents, index_of[n1]), root(&mut parents, index_of[n2]));
+            parents[r1] = r2;
+        }
+
+        let mut vertices: HashMap<usize, HashSet<N>> = HashMap::new();
+        let mut edges: HashMap<usize, Vec<HasAffiliation<N, E>>> = HashMap::new();
+        for edge_triple in affiliated_edges {
+            let component = root(&mut parents, index_of[&edge_triple.0]);
+            vertices.entry(component).or_default().extend([edge_triple.0, edge_triple.1]);
+            edges.entry(component).or_default().push(HasAffiliation::from_edge(edge_triple));
+        }
+
+        edges.into_iter()
+            .filter(|(component, edges)| vertices[component].len() == edges.len())
+            .map(|(_, edges)| edges)
+            .collect_vec()
+    }
+}
+
+impl<N, E> GraphSolver<'_, N, E>
+where
+    N: Terminus,
+    E: PartialEq + Eq + Hash + Copy + Drawn + Straight,
+{
+    /// Solve as in [`Self::solve`], but return a solution which is best by `objective`.
+    ///
+    /// Having found any solution, a unary counter over the literals which each add one to its cost is added, and the solver is asked again with the counter bounded below that cost, until it fails.
+    /// The last solution found is then optimal; the counter only goes as high as the first solution's cost.
+    pub fn minimize(&self, mut backend: impl Backend, objective: Objective) -> Result<Solution<N, E>, SolverFailure> {
+        let _limits = backend::watch(&self.options);
+        let (fixed_assumptions, mut next_aux_var) = self.load(&mut backend);
+        let mut best = self.next_solution(&mut backend)?.ok_or(SolverFailure::Inconsistent)?;
+        let mut cost = self.cost(objective, &best);
+        if cost == 0 {
+            return Ok(best);
+        }
+
+        // the counter takes its variables after every one the encoding allocated, whether or not it was used
+        let (clauses, costs) = self.cost_lits(objective, &mut next_aux_var);
+        clauses.iter().for_each(|clause| backend.add_clause(clause));
+        let (clauses, count) = unary_count(&costs, cost, &mut next_aux_var);
+        clauses.iter().for_each(|clause| backend.add_clause(clause));
+
+        while cost > 0 {
+            // no more than cost - 1
+            let mut assumptions = fixed_assumptions.clone();
+            assumptions.extend(self.selector_assumptions(|_| true));
+            assumptions.push(!count[cost - 1]);
+            backend.assume(&assumptions);
+
+            match self.next_solution(&mut backend)? {
+                None => break,
+                Some(better) => {
+                    cost = self.cost(objective, &better);
+                    best = better;
+                }
+            }
+        }
+
+        Ok(best)
+    }
+
+    /// Generate literals each of which adds one to the cost of a solution by `objective`, along with any clauses defining them.
+    ///
+    /// A literal is true in every model whose solution has the cost it stands for, but may be true otherwise, so only an upper bound on the count is meaningful.
+    fn cost_lits(&self, objective: Objective, next_aux_var: &mut usize) -> (Vec<Vec<Lit>>, Vec<Lit>) {
+        let used = |edge: HasAffiliation<N, E>| self.affiliation_var(edge, 0).negative();
+
+        match objective {
+            Objective::Length => (Vec::new(), self.graph.all_edges().map(|edge_triple| used(HasAffiliation::from_edge(edge_triple))).collect_vec()),
+            Objective::Bends => {
+                let mut clauses = Vec::new();
+                let mut bends = Vec::new();
+                for vertex in self.graph.nodes() {
+                    for (e1, e2) in self.bends_at(vertex) {
+                        // both edges used => a bend
+                        let bend = fresh_var(next_aux_var).positive();
+                        clauses.push(vec![!used(e1), !used(e2), bend]);
+                        bends.push(bend);
+                    }
+                }
+
+                (clauses, bends)
+            }
+        }
+    }
+
+    /// The cost of `solution` by `objective`.
+    fn cost(&self, objective: Objective, solution: &Solution<N, E>) -> usize {
+        match objective {
+            Objective::Length => self.graph.all_edges().filter(|edge_triple| solution[&HasAffiliation::from_edge(*edge_triple)] != 0).count(),
+            Objective::Bends => self.graph.nodes()
+                .flat_map(|vertex| self.bends_at(vertex))
+                .filter(|(e1, e2)| solution[e1] != 0 && solution[e2] != 0)
+                .count(),
+        }
+    }
+
+    /// Every pair of edges incident to `vertex` between which a path would bend.
+    fn bends_at(&self, vertex: N) -> Vec<(HasAffiliation<N, E>, HasAffiliation<N, E>)> {
+        self.incident_edges[&vertex].iter()
+            .tuple_combinations()
+            .filter(|(e1, e2)| match (e1, e2) {
+                (HasAffiliation::Edge { edge: a, .. }, HasAffiliation::Edge { edge: b, .. }) => !a.straight(b),
+                _ => unreachable!(),
+            })
+            .map(|(e1, e2)| (*e1, *e2))
+            .collect_vec()
+    }
+}
+
+impl<N, E> GraphSolver<'_, N, E>
+where
+    N: Terminus + Debug,
+    E: PartialEq + Eq + Hash + Copy + Drawn + Debug,
+{
+    /// Write the clauses which [`Self::solve`] starts from in DIMACS CNF format, with its assumptions as unit clauses at the end.
+    ///
+    /// A comment header names the vertex or edge and the affiliation behind each variable, and counts the assumptions; no comments follow the `p` line.
+    /// Clauses cutting detached cycles are only added while solving, so a model of this formula may still contain such cycles; [`Self::read_model`] checks for them.
+    pub fn write_dimacs(&self, out: impl Write) -> io::Result<()> {
+        let (formulae, mut assumptions, _) = self.encode();
+        assumptions.extend(self.selector_assumptions(|_| true));
+
+        let num_vars = formulae.iter()
+            .map(|formula| formula.var_count())
+            .chain(assumptions.iter().map(|lit| lit.index() + 1))
+            .max()
+            .unwrap_or(0);
+        let num_clauses = formulae.iter().map(|formula| formula.len()).sum::<usize>() + assumptions.len();
+
+        let mut out = BufWriter::new(out);
+        writeln!(out, "c Numberlink encoding by permanganate")?;
+        for (holder, aff) in self.affiliation_holders.iter().cartesian_product(self.valid_affiliations()) {
+            writeln!(out, "c {} {:?} affiliation {}", self.affiliation_var(*holder, aff).to_dimacs(), holder, aff)?;
+        }
+        for selector in 0..self.guards.selectors.len() {
+            writeln!(out, "c {} selector {}", self.selector_var(selector).to_dimacs(), selector)?;
+        }
+        if num_vars > self.aux_var_base() {
+            writeln!(out, "c {} and above auxiliary", Var::from_index(self.aux_var_base()).to_dimacs())?;
+        }
+        // strict readers reject comments among the clauses, so say up front where the assumptions begin
+        writeln!(out, "c last {} clauses assumptions", assumptions.len())?;
+
+        writeln!(out, "p cnf {} {}", num_vars, num_clauses)?;
+        for clause in formulae.iter().flat_map(|formula| formula.iter()) {
+            for lit in clause {
+                write!(out, "{} ", lit.to_dimacs())?;
+            }
+            writeln!(out, "0")?;
+        }
+        for lit in assumptions {
+            writeln!(out, "{} 0", lit.to_dimacs())?;
+        }
+
+        out.flush()
+    }
+}
+
+/// An iterator over the solutions of a graph, created by [`GraphSolver::solutions`].
+///
+/// A single SAT solver is kept alive throughout, and each solution found is blocked before searching for the next.
+pub struct Solutions<'gph, N, E, B = Solver<'static>>
+where
+    N: Terminus,
+{
+    graph_solver: GraphSolver<'gph, N, E>,
+    solver: B,
+    remaining: Option<usize>,
+    exhausted: bool,
+}
+
+impl<N, E, B> Iterator for Solutions<'_, N, E, B>
+where
+    N: Terminus,
+    E: PartialEq + Eq + Hash + Copy + Drawn,
+    B: Backend,
+{
+    type Item = Result<Solution<N, E>, SolverFailure>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exhausted || self.remaining == Some(0) {
+            return None;
+        }
+
+        let solution = match self.graph_solver.next_solution(&mut self.solver) {
+            Ok(Some(solution)) => solution,
+            Ok(None) => {
+                self.exhausted = true;
+                return None;
+            }
+            Err(reason) => {
+                self.exhausted = true;
+                return Some(Err(reason));
+            }
+        };
+
+        self.solver.add_clause(&self.graph_solver.blocking_clause(&solution));
+        self.remaining = self.remaining.map(|remaining| remaining - 1);
+
+        Some(Ok(solution))
+    }
 }
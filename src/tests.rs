@@ -1,10 +1,141 @@
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "sat")]
+    use std::collections::HashMap;
     use std::num::NonZero;
+    #[cfg(feature = "sat")]
+    use std::ops::RangeInclusive;
+    #[cfg(feature = "sat")]
+    use std::sync::{Arc, Mutex};
+    #[cfg(feature = "sat")]
+    use std::time::{Duration, Instant};
 
-    use crate::builder::{Builder, SquareBoardBuilder};
+    use itertools::Itertools;
+    #[cfg(feature = "sat")]
+    use strum::VariantArray;
+    use unordered_pair::UnorderedPair;
+    #[cfg(feature = "sat")]
+    use varisat::dimacs::DimacsParser;
+    #[cfg(feature = "sat")]
+    use varisat::Solver;
+
+    #[cfg(feature = "cdcl")]
+    use crate::backend::Cdcl;
+    #[cfg(feature = "sat")]
+    use crate::backend::{Counted, DimacsProcess};
+    use crate::builder::{Builder, BuilderInvalidReason, HexBoardBuilder, SquareBoardBuilder};
+    #[cfg(feature = "sat")]
+    use crate::builder::{Feature, SelfTouch};
     use crate::location::Location;
-    use crate::shape::SquareStep;
+    #[cfg(feature = "sat")]
+    use crate::options::{AffiliationEncoding, AtMostOne, SolveOptions};
+    use crate::propagation::{Deduction, Fact, Rule};
+    #[cfg(feature = "sat")]
+    use crate::rating::Technique;
+    #[cfg(feature = "sat")]
+    use crate::shape::{FullShape, HexStep, SquareStep};
+    #[cfg(any(feature = "sat", feature = "search"))]
+    use crate::graph::SolverFailure;
+    #[cfg(feature = "sat")]
+    use crate::graph::Uniqueness;
+    #[cfg(feature = "sat")]
+    use crate::trace::Reason;
+    #[cfg(all(feature = "sat", feature = "serde"))]
+    use crate::trace::Step;
+    #[cfg(feature = "sat")]
+    use crate::{Board, CancelToken, Hint, Objective, ProgressCallback, Session, Settled, PROGRESS_INTERVAL};
+
+    /// Flow Free classic pack level 1, which has a unique solution, ready to build or add to.
+    fn classic_level_1() -> SquareBoardBuilder {
+        let mut builder = SquareBoardBuilder::with_dims((NonZero::new(5).unwrap(), NonZero::new(5).unwrap()));
+        builder.add_termini('A', (Location(0, 0), Location(1, 4)))
+            .add_termini('B', (Location(2, 0), Location(1, 3)))
+            .add_termini('C', (Location(2, 1), Location(2, 4)))
+            .add_termini('D', (Location(4, 0), Location(3, 3)))
+            .add_termini('E', (Location(4, 1), Location(3, 4)));
+        builder
+    }
+
+    /// Flow Free bridges warps pack level 150, with warps on every side and a bridge, ready to build or add to.
+    #[cfg(feature = "sat")]
+    fn level_150() -> SquareBoardBuilder {
+        let mut builder = SquareBoardBuilder::with_dims((NonZero::new(9).unwrap(), NonZero::new(9).unwrap()));
+        builder.add_termini('A', (Location(6, 1), Location(7, 2)))
+            .add_termini('B', (Location(3, 2), Location(5, 6)))
+            .add_termini('C', (Location(3, 4), Location(5, 3)))
+            .add_termini('D', (Location(2, 7), Location(6, 3)))
+            .add_termini('E', (Location(6, 2), Location(7, 7)))
+            .add_termini('F', (Location(2, 6), Location(4, 8)))
+            .add_termini('G', (Location(1, 0), Location(0, 3)))
+            .add_termini('H', (Location(3, 1), Location(3, 3)))
+            .add_termini('I', (Location(0, 8), Location(3, 7)))
+            .add_termini('J', (Location(5, 5), Location(5, 8)))
+            .add_warp(Location(4, 0), None)
+            .add_warp(Location(4, 8), None)
+            .add_warp(Location(0, 4), None)
+            .add_warp(Location(8, 4), None)
+            .add_bridge(Location(4, 6));
+        builder
+    }
+
+    /// A 2x2 board whose two paths must cross, so which has no solution.
+    #[cfg(any(feature = "sat", feature = "search"))]
+    fn crossing() -> SquareBoardBuilder {
+        let mut builder = SquareBoardBuilder::with_dims((NonZero::new(2).unwrap(), NonZero::new(2).unwrap()));
+        builder.add_termini('A', (Location(0, 0), Location(1, 1)))
+            .add_termini('B', (Location(1, 0), Location(0, 1)));
+        builder
+    }
+
+    /// A 4x4 board with two solutions, in which A and B swap which goes round the outside.
+    #[cfg(feature = "sat")]
+    fn loose() -> SquareBoardBuilder {
+        let mut builder = SquareBoardBuilder::with_dims((NonZero::new(4).unwrap(), NonZero::new(4).unwrap()));
+        builder.add_termini('A', (Location(3, 3), Location(1, 2)))
+            .add_termini('B', (Location(2, 2), Location(0, 3)))
+            .add_termini('C', (Location(1, 1), Location(2, 1)));
+        builder
+    }
+
+    /// A 7x7 board with one path between neighbouring cells, which cannot be filled, since every such path covers an even number of cells; a SAT solver is slow to see this.
+    #[cfg(feature = "sat")]
+    fn impossible() -> SquareBoardBuilder {
+        let mut builder = SquareBoardBuilder::with_dims((NonZero::new(7).unwrap(), NonZero::new(7).unwrap()));
+        builder.add_termini('A', (Location(0, 0), Location(1, 0)));
+        builder
+    }
+
+    /// A 3x2 board whose path may go straight along the top row or round through the bottom one, leaving the other row empty, with `length` if given.
+    #[cfg(feature = "sat")]
+    fn top_or_bottom(length: Option<RangeInclusive<usize>>) -> SquareBoardBuilder {
+        let mut builder = SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(2).unwrap()));
+        let termini = (Location(0, 0), Location(2, 0));
+        match length {
+            None => builder.add_termini('A', termini),
+            Some(length) => builder.add_termini_with_length('A', termini, length),
+        }.allow_empty(true);
+        builder
+    }
+
+    /// Solve `board` with every combination of encodings, with and without propagation, check that each gives the same solution, and return it.
+    #[cfg(feature = "sat")]
+    fn solve_every_way<Sh: FullShape>(board: Board<Sh>) -> Board<Sh> {
+        let solutions = AtMostOne::VARIANTS.iter()
+            .cartesian_product(AffiliationEncoding::VARIANTS)
+            .cartesian_product([false, true])
+            .map(|((at_most_one, affiliations), propagate)| board.clone()
+                .solve(SolveOptions { at_most_one: *at_most_one, affiliations: *affiliations, propagate, ..Default::default() })
+                .unwrap())
+            .collect_vec();
+
+        for solution in &solutions[1..] {
+            assert_eq!(format!("{}", solution), format!("{}", solutions[0]));
+        }
+        #[cfg(feature = "search")]
+        assert_eq!(format!("{}", board.clone().solve_by_search().unwrap()), format!("{}", solutions[0]));
+
+        solutions.into_iter().next().unwrap()
+    }
 
     #[test]
     fn remove_termini() {
@@ -20,20 +151,32 @@ mod tests {
 .....
 .....
 ");
-    }
 
-    #[test]
-    fn solve_most_basic() {
-        // flow free classic pack level 1
+        // restrictions naming the removed path forget it, so a new path may use the cells it kept to
         let board = SquareBoardBuilder::with_dims((NonZero::new(5).unwrap(), NonZero::new(5).unwrap()))
             .add_termini('A', (Location(0, 0), Location(1, 4)))
-            .add_termini('B', (Location(2, 0), Location(1, 3)))
-            .add_termini('C', (Location(2, 1), Location(2, 4)))
-            .add_termini('D', (Location(4, 0), Location(3, 3)))
-            .add_termini('E', (Location(4, 1), Location(3, 4)))
+            .add_termini('B', (Location(4, 0), Location(4, 1)))
+            .allow_only(Location(2, 2), vec!['B'])
+            .forbid(Location(3, 3), vec!['A', 'B'])
+            .pop_termini()
+            .add_termini('C', (Location(2, 2), Location(4, 4)))
             .build()
             .unwrap();
 
+        assert_eq!(format!("{}", board), "A....
+.....
+..C..
+.....
+.A..C
+no A at (3, 3)
+");
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn solve_most_basic() {
+        let board = classic_level_1().build().unwrap();
+
         assert_eq!(format!("{}", board), "A.B.D
 ..C.E
 .....
@@ -41,7 +184,7 @@ mod tests {
 .ACE.
 ");
 
-        let solved = board.solve().unwrap();
+        let solved = solve_every_way(board);
         assert_eq!(format!("{}", solved), "AbBdD
 abCdE
 abcde
@@ -51,6 +194,7 @@ aACEe
     }
 
     #[test]
+    #[cfg(feature = "sat")]
     fn solve_large_simple_square() {
         // flow free extreme pack 2 12x12 level 13
         let board = SquareBoardBuilder::with_dims((NonZero::new(12).unwrap(), NonZero::new(12).unwrap()))
@@ -78,7 +222,7 @@ aACEe
 C...AB.E....
 ");
 
-        let solved = board.solve().unwrap();
+        let solved = solve_every_way(board);
         assert_eq!(format!("{}", solved), "ccccceeeeeee
 caaacebbbbbe
 caDacebFffbe
@@ -95,6 +239,7 @@ CaaaABbEeeee
     }
 
     #[test]
+    #[cfg(feature = "sat")]
     fn simple_with_bridge() {
         // flow free bridges starter pack 5x5 level 2
         let board = SquareBoardBuilder::with_dims((NonZero::new(5).unwrap(), NonZero::new(5).unwrap()))
@@ -114,7 +259,7 @@ CaaaABbEeeee
 CB...
 ");
 
-        let solved = board.solve().unwrap();
+        let solved = solve_every_way(board);
         assert_eq!(format!("{}", solved), "CDdAE
 ca+ae
 caDee
@@ -124,6 +269,7 @@ CBbbb
     }
 
     #[test]
+    #[cfg(feature = "sat")]
     fn adjacent_bridges() {
         // flow free bridges hashed pack 7x7 level 1
         let board = SquareBoardBuilder::with_dims((NonZero::new(7).unwrap(), NonZero::new(7).unwrap()))
@@ -149,7 +295,7 @@ AE..F..
 .....AB
 ");
 
-        let solved = board.solve().unwrap();
+        let solved = solve_every_way(board);
         assert_eq!(format!("{}", solved), "Bcccccc
 bCeeeeC
 bDefFeE
@@ -161,6 +307,7 @@ aaaaaAB
     }
 
     #[test]
+    #[cfg(feature = "sat")]
     fn simple_with_warp() {
         // flow free warps starter pack level 2
         let board = SquareBoardBuilder::with_dims((NonZero::new(5).unwrap(), NonZero::new(3).unwrap()))
@@ -177,7 +324,7 @@ aaaaaAB
 C...B
 ");
 
-        let solved = board.solve().unwrap();
+        let solved = solve_every_way(board);
         assert_eq!(format!("{}", solved), "AaaaA
 dDCBD
 CccbB
@@ -185,6 +332,7 @@ CccbB
     }
 
     #[test]
+    #[cfg(feature = "sat")]
     fn warp_with_holes() {
         // flow free warps starter pack level 1
         let board = SquareBoardBuilder::with_dims((NonZero::new(6).unwrap(), NonZero::new(3).unwrap()))
@@ -207,7 +355,7 @@ AC.CA.
 .D.D..
 ");
 
-        let solved = board.solve().unwrap();
+        let solved = solve_every_way(board);
         assert_eq!(format!("{}", solved), ".BbB..
 ACcCAa
 .DdD..
@@ -215,6 +363,7 @@ ACcCAa
     }
 
     #[test]
+    #[cfg(feature = "sat")]
     fn walls() {
         // flow free pockets pack level 1
         let board = SquareBoardBuilder::with_dims((NonZero::new(8).unwrap(), NonZero::new(8).unwrap()))
@@ -242,7 +391,7 @@ ACcCAa
 ...E...C
 ");
 
-        let solved = board.solve().unwrap();
+        let solved = solve_every_way(board);
         assert_eq!(format!("{}", solved), "eeeeaaaa
 eaaEadda
 eAaaaDda
@@ -255,26 +404,9 @@ eeeEcccC
     }
 
     #[test]
+    #[cfg(feature = "sat")]
     fn warps_and_bridges() {
-        // flow free bridges warps pack level 150
-        let board = SquareBoardBuilder::with_dims((NonZero::new(9).unwrap(), NonZero::new(9).unwrap()))
-            .add_termini('A', (Location(6, 1), Location(7, 2)))
-            .add_termini('B', (Location(3, 2), Location(5, 6)))
-            .add_termini('C', (Location(3, 4), Location(5, 3)))
-            .add_termini('D', (Location(2, 7), Location(6, 3)))
-            .add_termini('E', (Location(6, 2), Location(7, 7)))
-            .add_termini('F', (Location(2, 6), Location(4, 8)))
-            .add_termini('G', (Location(1, 0), Location(0, 3)))
-            .add_termini('H', (Location(3, 1), Location(3, 3)))
-            .add_termini('I', (Location(0, 8), Location(3, 7)))
-            .add_termini('J', (Location(5, 5), Location(5, 8)))
-            .add_warp(Location(4, 0), None)
-            .add_warp(Location(4, 8), None)
-            .add_warp(Location(0, 4), None)
-            .add_warp(Location(8, 4), None)
-            .add_bridge(Location(4, 6))
-            .build()
-            .unwrap();
+        let board = level_150().build().unwrap();
 
         assert_eq!(format!("{}", board), ".G.......
 ...H..A..
@@ -287,7 +419,7 @@ G..H.CD..
 I...FJ...
 ");
 
-        let solved = board.solve().unwrap();
+        let solved = solve_every_way(board);
         assert_eq!(format!("{}", solved), "gGfffeeee
 gffHheAae
 gfbBheEAe
@@ -299,4 +431,877 @@ ddDIjjdEd
 IiiiFJddd
 ");
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn hex_most_basic() {
+        let board = HexBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(4).unwrap()))
+            .add_termini('A', (Location(0, 0), Location(0, 3)))
+            .add_termini('B', (Location(0, 1), Location(1, 3)))
+            .add_termini('C', (Location(1, 0), Location(2, 2)))
+            .add_termini('D', (Location(2, 0), Location(2, 3)))
+            .build()
+            .unwrap();
+
+        assert_eq!(format!("{}", board), "A   C   D
+  B   .   .
+.   .   C
+  A   B   D
+");
+
+        let solved = solve_every_way(board);
+        assert_eq!(format!("{}", solved), "A   C   D
+  B   c   d
+a   b   C
+  A   B   D
+");
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn hex_walls_and_holes() {
+        let board = HexBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(4).unwrap()))
+            .add_termini('A', (Location(0, 0), Location(0, 3)))
+            .add_termini('B', (Location(0, 1), Location(1, 3)))
+            .add_termini('D', (Location(2, 0), Location(2, 3)))
+            .drop_location(Location(1, 0))
+            .drop_location(Location(1, 1))
+            .disconnect(UnorderedPair::from((Location(2, 0), Location(2, 2))))
+            .disconnect_around(Location(2, 1), vec![HexStep::Down])
+            .build()
+            .unwrap();
+
+        let solved = solve_every_way(board);
+        assert_eq!(format!("{}", solved), "A   .   D
+  B   .   d
+a   b   d
+  A   B   D
+");
+    }
+
+    #[test]
+    fn hex_bounds() {
+        let dims = (NonZero::new(3).unwrap(), NonZero::new(5).unwrap());
+
+        // the cell above this one is off the board
+        assert!(matches!(
+            HexBoardBuilder::with_dims(dims).add_bridge(Location(1, 1)).is_valid().map(Vec::as_slice),
+            Some([BuilderInvalidReason::FeatureOutOfBounds])
+        ));
+        assert!(HexBoardBuilder::with_dims(dims).add_bridge(Location(1, 2)).is_valid().is_none());
+        assert!(matches!(
+            HexBoardBuilder::with_dims(dims).add_termini('A', (Location(0, 0), Location(3, 0))).is_valid().map(Vec::as_slice),
+            Some([BuilderInvalidReason::FeatureOutOfBounds])
+        ));
+    }
+
+    #[test]
+    fn single_cell() {
+        let dims = (NonZero::new(1).unwrap(), NonZero::new(1).unwrap());
+
+        // the only location has no neighbors, so no edge ever puts it on the board
+        assert_eq!(format!("{}", SquareBoardBuilder::with_dims(dims).build().unwrap()), ".\n");
+        assert_eq!(format!("{}", HexBoardBuilder::with_dims(dims).build().unwrap()), ".\n");
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn unique_most_basic() {
+        let board = classic_level_1().build().unwrap();
+
+        match board.check_unique().unwrap() {
+            Uniqueness::Unique(solved) => assert_eq!(format!("{}", solved), "AbBdD
+abCdE
+abcde
+aBcDe
+aACEe
+"),
+            _ => panic!("expected a unique solution"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn not_unique() {
+        let board = loose().build().unwrap();
+
+        match board.check_unique().unwrap() {
+            Uniqueness::Multiple(a, b) => assert_eq!([format!("{}", a), format!("{}", b)].into_iter().sorted().collect_vec(), vec!["aaaa
+aCCa
+aABa
+BbbA
+", "bbbb
+bCCb
+bABb
+BaaA
+"]),
+            _ => panic!("expected multiple solutions"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn backbone_unique() {
+        let board = classic_level_1().build().unwrap();
+
+        let backbone = board.backbone().unwrap();
+        assert_eq!(backbone.len(), 25);
+        assert_eq!(backbone[&Location(1, 0)], Settled {
+            display: Some('B'),
+            exits: HashMap::from([(SquareStep::Left, false), (SquareStep::Right, true), (SquareStep::Down, true)]),
+        });
+        // a unique solution settles everything
+        assert!(backbone.values().all(|settled| settled.display.is_some()));
+        assert_eq!(backbone.values().map(|settled| settled.exits.len()).sum::<usize>(), 2 * board.graph.edge_count());
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn backbone_loose() {
+        let board = loose().build().unwrap();
+
+        let backbone = board.backbone().unwrap();
+        let heat_map = (0..4)
+            .map(|y| (0..4).map(|x| backbone[&Location(x, y)].display.unwrap_or('?')).collect::<String>() + "\n")
+            .collect::<String>();
+        assert_eq!(heat_map, "????
+?CC?
+?AB?
+B??A
+");
+
+        // every location settled by the backbone looks the same in every solution, and no other
+        let solutions = board.solutions(None).map(|solved| format!("{}", solved.unwrap())).collect_vec();
+        let agreed = solutions[0].chars()
+            .enumerate()
+            .map(|(index, c)| match solutions.iter().all(|other| other.chars().nth(index) == Some(c)) {
+                true => c.to_ascii_uppercase(),
+                false if c == '\n' => c,
+                false => '?',
+            })
+            .collect::<String>();
+        assert_eq!(heat_map, agreed);
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn rate_boards() {
+        let easy = classic_level_1().build().unwrap();
+
+        let easy_rating = easy.rate().unwrap();
+        // forced moves alone fill a first level, and each counts once
+        assert_eq!(easy_rating.uses.keys().collect_vec(), vec![&Technique::ForcedMove]);
+        assert_eq!(easy_rating.score, easy_rating.uses[&Technique::ForcedMove] * Technique::ForcedMove.weight());
+        assert_eq!((easy_rating.lookahead_depth, easy_rating.complete), (0, true));
+        // varisat's conflicts are only counted on request
+        assert!(easy_rating.sat.is_none());
+        assert!(easy.rate_with_backend(|| Counted::new(Solver::new())).unwrap().sat.is_some_and(|sat| sat.decisions.is_none()));
+
+        let hard = level_150().build().unwrap();
+
+        let hard_rating = hard.rate().unwrap();
+        // a larger board needs heavier techniques, but forced moves still do most of the work
+        assert!(hard_rating.uses[&Technique::Bottleneck] > 0 && hard_rating.uses[&Technique::Lookahead] > 0);
+        assert!(hard_rating.uses[&Technique::ForcedMove] > hard_rating.uses[&Technique::Bottleneck] + hard_rating.uses[&Technique::Lookahead]);
+        assert!(hard_rating.lookahead_depth >= 1 && hard_rating.complete);
+        assert!(hard_rating.score > easy_rating.score);
+        assert!(hard.rate_with_backend(|| Counted::new(Solver::new())).unwrap().sat.is_some_and(|sat| sat.conflicts > 0));
+
+        // two solutions, so no technique can settle which colour goes round the outside
+        let loose = loose().build().unwrap();
+
+        assert!(!loose.rate().unwrap().complete);
+
+        #[cfg(feature = "cdcl")]
+        assert!(hard.rate_with_backend(Cdcl::new).unwrap().sat.is_some_and(|sat| sat.decisions.is_some()));
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn trace_steps() {
+        let easy = classic_level_1().build().unwrap();
+
+        let steps = easy.trace().unwrap();
+        assert!(steps.iter().all(|step| matches!(step.reason, Reason::Rule(_)) && step.facts.len() == 1));
+        assert_eq!(steps.last().unwrap().board, format!("{}", easy.clone().solve(SolveOptions::default()).unwrap()));
+
+        // each step shows the cells it fills
+        let first = steps.iter().find(|step| matches!(step.facts[0], Fact::Affiliated(..))).unwrap();
+        assert_eq!(first.facts, vec![Fact::Affiliated(Location(0, 4), 'A')]);
+        assert_eq!(first.board, "A.B.D
+..C.E
+.....
+.B.D.
+aACE.
+");
+
+        // two solutions, so one connection must be guessed
+        let loose = loose().build().unwrap();
+
+        let steps = loose.trace().unwrap();
+        let reasons = steps.iter().filter(|step| !matches!(step.reason, Reason::Rule(_))).map(|step| &step.reason).collect_vec();
+        assert_eq!(reasons, vec![&Reason::Contradiction { depth: 1 }, &Reason::Guess]);
+        assert!(["aaaa\naCCa\naABa\nBbbA\n", "bbbb\nbCCb\nbABb\nBaaA\n"].contains(&steps.last().unwrap().board.as_str()));
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&steps[0]).unwrap();
+            assert_eq!(json, r#"{"reason":{"Rule":"TwoOpenNeighbours"},"facts":[{"Connected":[[0,0],[1,0]]}],"board":"....\n.CC.\n.AB.\nB..A\n"}"#);
+            assert_eq!(serde_json::from_str::<Step>(&json).unwrap(), steps[0]);
+        }
+
+        // lines for what no cell can show are printed after every step too
+        let checkpoint = top_or_bottom(None).add_checkpoint('A', Location(1, 1)).build().unwrap();
+        let steps = checkpoint.trace().unwrap();
+        assert!(!steps.is_empty() && steps.iter().all(|step| step.board.ends_with("\ncheckpoint A at (1, 1)\n")));
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn solve_limits() {
+        let board = impossible().build().unwrap();
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let progress = {
+            let reports = reports.clone();
+            ProgressCallback::new(move |progress| reports.lock().unwrap().push(progress.conflicts))
+        };
+        let options = SolveOptions { conflict_budget: Some(2 * PROGRESS_INTERVAL), progress: Some(progress), ..Default::default() };
+        assert!(matches!(board.clone().solve(options), Err(SolverFailure::Timeout)));
+        // each report comes at a later multiple of the interval, none beyond the budget
+        let reported = reports.lock().unwrap();
+        assert!(!reported.is_empty() && reported.iter().all(|conflicts| conflicts % PROGRESS_INTERVAL == 0 && *conflicts <= 2 * PROGRESS_INTERVAL));
+        assert!(reported.iter().tuple_windows().all(|(earlier, later)| earlier < later));
+
+        // cancelling from the progress callback stops at the next conflict
+        let cancel = CancelToken::new();
+        let progress = {
+            let cancel = cancel.clone();
+            ProgressCallback::new(move |_| cancel.cancel())
+        };
+        let options = SolveOptions { cancel: Some(cancel.clone()), progress: Some(progress), ..Default::default() };
+        assert!(matches!(board.clone().solve(options), Err(SolverFailure::Cancelled)));
+        assert!(cancel.is_cancelled());
+
+        let options = SolveOptions { deadline: Some(Instant::now()), ..Default::default() };
+        assert!(matches!(board.clone().solve(options), Err(SolverFailure::Timeout)));
+
+        #[cfg(feature = "cdcl")]
+        {
+            let options = SolveOptions { conflict_budget: Some(PROGRESS_INTERVAL), ..Default::default() };
+            assert!(matches!(board.clone().solve_with_backend(options, Cdcl::new), Err(SolverFailure::Timeout)));
+        }
+
+        // limits which are not reached change nothing
+        let options = SolveOptions { conflict_budget: Some(0), deadline: Some(Instant::now() + Duration::from_secs(60)), cancel: Some(CancelToken::new()), ..Default::default() };
+        let easy = SquareBoardBuilder::with_dims((NonZero::new(2).unwrap(), NonZero::new(2).unwrap()))
+            .add_termini('A', (Location(0, 0), Location(1, 0)))
+            .add_termini('B', (Location(0, 1), Location(1, 1)))
+            .build()
+            .unwrap();
+        assert_eq!(format!("{}", easy.solve(options).unwrap()), "AA\nBB\n");
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn solve_allowing_empty() {
+        let mut builder = SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(2).unwrap()));
+        builder.add_termini('A', (Location(0, 0), Location(2, 0)))
+            .add_termini('B', (Location(0, 1), Location(1, 1)));
+        assert!(matches!(builder.build().unwrap().solve(SolveOptions::default()), Err(SolverFailure::Conflict(_))));
+
+        let board = builder.allow_empty(true).build().unwrap();
+        assert!(board.propagate().is_empty());
+        match board.check_unique().unwrap() {
+            Uniqueness::Unique(solved) => assert_eq!(format!("{}", solved), "AaA\nBB.\n"),
+            _ => panic!("board should have exactly one solution"),
+        }
+        assert_eq!(format!("{}", solve_every_way(board)), "AaA\nBB.\n");
+
+        let bridged = SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(3).unwrap()))
+            .add_termini('A', (Location(1, 0), Location(1, 2)))
+            .add_termini('B', (Location(0, 1), Location(2, 1)))
+            .add_bridge(Location(1, 1))
+            .allow_empty(true)
+            .build()
+            .unwrap();
+        assert_eq!(format!("{}", bridged.solve(SolveOptions::default()).unwrap()), ".A.\nB+B\n.A.\n");
+
+        // either way round the warp leaves a cell empty
+        let warped = SquareBoardBuilder::with_dims((NonZero::new(5).unwrap(), NonZero::new(1).unwrap()))
+            .add_termini('A', (Location(1, 0), Location(3, 0)))
+            .add_warp(Location(0, 0), Some(SquareStep::Left))
+            .allow_empty(true)
+            .build()
+            .unwrap();
+        let solutions = warped.solutions(None).map(|solution| format!("{}", solution.unwrap())).sorted().collect_vec();
+        assert_eq!(solutions, vec![".AaA.\n", "aA.Aa\n"]);
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn solve_touching_self() {
+        // the only way round a 2x2 board runs the path alongside itself and fills a block
+        let mut builder = SquareBoardBuilder::with_dims((NonZero::new(2).unwrap(), NonZero::new(2).unwrap()));
+        builder.add_termini('A', (Location(0, 0), Location(1, 0)));
+        assert!(builder.build().unwrap().solve(SolveOptions::default()).is_err());
+        builder.self_touch(SelfTouch { orthogonal: false, blocks: true });
+        assert!(builder.build().unwrap().solve(SolveOptions::default()).is_err());
+
+        let board = builder.self_touch(SelfTouch { orthogonal: false, blocks: false }).build().unwrap();
+        assert!(board.propagate().is_empty());
+        assert_eq!(format!("{}", solve_every_way(board)), "AA\naa\n");
+
+        // turning back between the ends of the top row fills a block, which only counts once blocks are allowed
+        let mut builder = top_or_bottom(None);
+        let count = |builder: &SquareBoardBuilder| builder.build().unwrap().solutions(None).count();
+        assert_eq!(count(&builder), 2);
+        assert_eq!(count(builder.self_touch(SelfTouch { orthogonal: false, blocks: true })), 2);
+        assert_eq!(count(builder.self_touch(SelfTouch { orthogonal: false, blocks: false })), 4);
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn solve_with_restrictions() {
+        let builder = top_or_bottom(None);
+
+        let checkpoint = builder.clone().add_checkpoint('A', Location(1, 1)).build().unwrap();
+        assert_eq!(format!("{}", checkpoint), "A.A\n...\ncheckpoint A at (1, 1)\n");
+        assert_eq!(format!("{}", solve_every_way(checkpoint)), "A.A\naaa\ncheckpoint A at (1, 1)\n");
+
+        let forbidden = builder.clone().forbid(Location(1, 1), vec!['A']).build().unwrap();
+        assert_eq!(format!("{}", solve_every_way(forbidden)), "AaA\n...\nno A at (1, 1)\n");
+
+        let board = SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(2).unwrap()))
+            .add_termini('A', (Location(0, 0), Location(2, 0)))
+            .add_termini('B', (Location(0, 1), Location(2, 1)))
+            .allow_only(Location(1, 0), vec!['B'])
+            .build()
+            .unwrap();
+        match board.solve(SolveOptions::default()) {
+            Err(SolverFailure::Conflict(features)) => assert!(features.contains(&Feature::Allowed { location: Location(1, 0), displays: vec!['B'] })),
+            _ => panic!("board should be unsolvable"),
+        }
+
+        let reasons = |builder: &mut SquareBoardBuilder| builder.is_valid().cloned().unwrap_or_default();
+        assert!(matches!(reasons(builder.clone().forbid(Location(0, 0), vec!['A']))[..], [BuilderInvalidReason::ForbiddenTerminus]));
+        assert!(matches!(reasons(builder.clone().add_checkpoint('B', Location(1, 0)))[..], [BuilderInvalidReason::UnknownDisplay]));
+        assert!(matches!(reasons(builder.clone().add_termini('B', (Location(0, 1), Location(2, 1))).add_checkpoint('B', Location(0, 0)))[..], [BuilderInvalidReason::CheckpointConflict]));
+        assert!(matches!(reasons(builder.clone().add_checkpoint('A', Location(1, 1)).forbid(Location(1, 1), vec!['A']))[..], [BuilderInvalidReason::CheckpointConflict]));
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn solve_one_way() {
+        let builder = top_or_bottom(None);
+        assert_eq!(builder.build().unwrap().travel(), vec![('A', (Location(0, 0), Location(2, 0)))]);
+
+        // the top row cannot be crossed either way
+        let one_way = builder.clone()
+            .add_one_way(Location(0, 0), SquareStep::Right)
+            .add_one_way(Location(2, 0), SquareStep::Left)
+            .build()
+            .unwrap();
+        let solved = solve_every_way(one_way);
+        assert_eq!(format!("{}", solved), "A.A\naaa\n");
+        assert_eq!(solved.travel(), vec![('A', (Location(0, 0), Location(2, 0)))]);
+
+        let arrow = builder.clone().add_arrow(Location(1, 1), SquareStep::Left).build().unwrap();
+        assert!(matches!(arrow.check_unique(), Ok(Uniqueness::Unique(_))));
+        let solved = solve_every_way(arrow);
+        assert_eq!(format!("{}", solved), "A.A\naaa\n");
+        assert_eq!(solved.travel(), vec![('A', (Location(2, 0), Location(0, 0)))]);
+
+        let opposed = builder.clone()
+            .add_arrow(Location(1, 1), SquareStep::Left)
+            .add_one_way(Location(0, 0), SquareStep::Down)
+            .build()
+            .unwrap();
+        match opposed.solve(SolveOptions::default()) {
+            Err(SolverFailure::Conflict(features)) => assert!(features.iter().all(|feature| matches!(feature, Feature::Arrow { .. } | Feature::OneWay { .. }))),
+            _ => panic!("board should be unsolvable"),
+        }
+
+        assert!(matches!(
+            builder.clone().add_arrow(Location(0, 1), SquareStep::Right).is_valid().map(Vec::as_slice),
+            Some([BuilderInvalidReason::FeatureOutOfBounds])
+        ));
+
+        // a one-way edge or arrow which no path can cross is rejected, whichever is placed first
+        let blocked = |builder: &mut SquareBoardBuilder| matches!(builder.is_valid().map(Vec::as_slice), Some([BuilderInvalidReason::BlockedDirection]));
+        assert!(blocked(builder.clone().add_arrow(Location(1, 0), SquareStep::Right).add_termini('B', (Location(1, 0), Location(1, 1)))));
+        assert!(blocked(builder.clone().disconnect(UnorderedPair(Location(0, 1), Location(1, 1))).add_arrow(Location(1, 1), SquareStep::Left)));
+        assert!(blocked(builder.clone().add_arrow(Location(1, 1), SquareStep::Left).disconnect(UnorderedPair(Location(0, 1), Location(1, 1)))));
+        assert!(blocked(builder.clone().add_one_way(Location(0, 1), SquareStep::Right).drop_location(Location(1, 1))));
+        assert!(blocked(builder.clone().disconnect(UnorderedPair(Location(0, 1), Location(1, 1))).add_one_way(Location(0, 1), SquareStep::Right)));
+        assert!(blocked(SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(3).unwrap()))
+            .add_bridge(Location(1, 1))
+            .add_arrow(Location(1, 1), SquareStep::Down)));
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn solve_with_length() {
+        // the path goes through 3 cells along the top row, or 5 round through the bottom one
+        let with_length = |length| top_or_bottom(Some(length));
+
+        let short = solve_every_way(with_length(0..=3).build().unwrap());
+        assert_eq!(format!("{}", short), "AaA\n...\nlength of A from 0 to 3\n");
+        assert_eq!(short.lengths(), vec![('A', 3)]);
+
+        let long = solve_every_way(with_length(4..=6).build().unwrap());
+        assert_eq!(format!("{}", long), "A.A\naaa\nlength of A from 4 to 6\n");
+        assert_eq!(long.lengths(), vec![('A', 5)]);
+
+        match with_length(4..=4).build().unwrap().solve(SolveOptions::default()) {
+            Err(SolverFailure::Conflict(features)) => assert!(features.contains(&Feature::Length { display: 'A', length: 4..=4 })),
+            _ => panic!("board should be unsolvable"),
+        }
+
+        assert!(matches!(with_length(0..=1).is_valid().map(Vec::as_slice), Some([BuilderInvalidReason::BadLength])));
+
+        // a length belongs to its own termini, not to later ones which display alike
+        let shared = SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(3).unwrap()))
+            .add_termini_with_length('A', (Location(0, 0), Location(2, 0)), 4..=6)
+            .add_termini('A', (Location(0, 2), Location(2, 2)))
+            .allow_empty(true)
+            .build()
+            .unwrap();
+        assert_eq!(format!("{}", solve_every_way(shared)), "A.A\naaa\nAaA\nlength of A from 4 to 6\n");
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn solve_objectives() {
+        let board = SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(3).unwrap()))
+            .add_termini('A', (Location(0, 0), Location(2, 2)))
+            .allow_empty(true)
+            .build()
+            .unwrap();
+
+        // a shortest path crosses the corner, bending anywhere along the way
+        let shortest = board.clone().solve(SolveOptions { objective: Some(Objective::Length), ..Default::default() }).unwrap();
+        assert_eq!(format!("{}", shortest).matches('a').count(), 3);
+
+        // of those, only the two round the edge bend once, and nothing longer does
+        for affiliations in AffiliationEncoding::VARIANTS {
+            let options = SolveOptions { objective: Some(Objective::Bends), affiliations: *affiliations, ..Default::default() };
+            let straightest = format!("{}", board.clone().solve(options).unwrap());
+            assert!(["Aaa\n..a\n..A\n", "A..\na..\naaA\n"].contains(&straightest.as_str()), "{}", straightest);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn solve_portfolio() {
+        let board = classic_level_1().build().unwrap();
+        let expected = format!("{}", board.clone().solve(SolveOptions::default()).unwrap());
+
+        // the order of variables changes nothing but the search
+        let seeded = board.clone().solve(SolveOptions { seed: 7, ..Default::default() }).unwrap();
+        assert_eq!(format!("{}", seeded), expected);
+        let raced = board.solve(SolveOptions { portfolio: 8, ..Default::default() }).unwrap();
+        assert_eq!(format!("{}", raced), expected);
+
+        let crossing = crossing().build().unwrap();
+        assert!(matches!(crossing.solve(SolveOptions { portfolio: 3, ..Default::default() }), Err(SolverFailure::Conflict(_))));
+
+        // every racer counts towards the same budget
+        let impossible = impossible().build().unwrap();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let progress = {
+            let reports = reports.clone();
+            ProgressCallback::new(move |progress| reports.lock().unwrap().push(progress.conflicts))
+        };
+        let options = SolveOptions { conflict_budget: Some(2 * PROGRESS_INTERVAL), progress: Some(progress), portfolio: 3, ..Default::default() };
+        assert!(matches!(impossible.solve(options), Err(SolverFailure::Timeout)));
+        assert_eq!(reports.lock().unwrap().iter().sorted().collect_vec(), vec![&PROGRESS_INTERVAL, &(2 * PROGRESS_INTERVAL)]);
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn solve_components() {
+        // the middle column splits the board in two, each side solved with its own colours
+        let board = SquareBoardBuilder::with_dims((NonZero::new(5).unwrap(), NonZero::new(2).unwrap()))
+            .drop_location(Location(2, 0))
+            .drop_location(Location(2, 1))
+            .add_termini('A', (Location(0, 0), Location(1, 0)))
+            .add_termini('B', (Location(0, 1), Location(1, 1)))
+            .add_termini('C', (Location(3, 0), Location(4, 0)))
+            .add_termini('D', (Location(3, 1), Location(4, 1)))
+            .build()
+            .unwrap();
+        let solved = board.solve(SolveOptions::default()).unwrap();
+        assert_eq!(format!("{}", solved), "AA.CC\nBB.DD\n");
+
+        // a colour with a terminus on each side cannot be joined, which is known before solving either side
+        let split = SquareBoardBuilder::with_dims((NonZero::new(5).unwrap(), NonZero::new(2).unwrap()))
+            .drop_location(Location(2, 0))
+            .drop_location(Location(2, 1))
+            .add_termini('A', (Location(0, 0), Location(4, 0)))
+            .add_termini('B', (Location(0, 1), Location(1, 1)))
+            .add_termini('C', (Location(3, 1), Location(4, 1)))
+            .build()
+            .unwrap();
+        let blamed = vec![
+            Feature::Termini { display: 'A', locations: (Location(0, 0), Location(4, 0)) },
+            Feature::DroppedLocation(Location(2, 0)),
+            Feature::DroppedLocation(Location(2, 1)),
+        ];
+        assert!(matches!(split.components(), Err(SolverFailure::Conflict(features)) if features == blamed));
+        assert!(matches!(split.solve(SolveOptions::default()), Err(SolverFailure::Conflict(features)) if features == blamed));
+
+        // likewise, a segment drawn on the side without its termini is blamed at once
+        let stray = SquareBoardBuilder::with_dims((NonZero::new(5).unwrap(), NonZero::new(2).unwrap()))
+            .drop_location(Location(2, 0))
+            .drop_location(Location(2, 1))
+            .add_termini('A', (Location(0, 0), Location(1, 0)))
+            .add_termini('B', (Location(0, 1), Location(1, 1)))
+            .add_termini('C', (Location(3, 0), Location(3, 1)))
+            .draw('A', UnorderedPair(Location(4, 0), Location(4, 1)))
+            .build()
+            .unwrap();
+        let drawn = Feature::Drawn { display: 'A', locations: UnorderedPair(Location(4, 0), Location(4, 1)) };
+        assert!(matches!(stray.solve(SolveOptions::default()), Err(SolverFailure::Conflict(features)) if features == vec![drawn]));
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn unique_unsolvable() {
+        let board = crossing().build().unwrap();
+
+        assert!(matches!(board.check_unique().unwrap(), Uniqueness::Unsolvable));
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn enumerate_solutions() {
+        let board = loose().build().unwrap();
+
+        let mut solutions = board.solutions(None)
+            .map(|solved| format!("{}", solved.unwrap()))
+            .collect::<Vec<_>>();
+        solutions.sort();
+        assert_eq!(solutions, vec!["aaaa
+aCCa
+aABa
+BbbA
+", "bbbb
+bCCb
+bABb
+BaaA
+"]);
+
+        assert_eq!(board.solutions(Some(1)).count(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn enumerate_unique() {
+        let board = classic_level_1().build().unwrap();
+
+        assert_eq!(board.solutions(Some(5)).count(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn no_detached_cycles() {
+        // the local constraints alone are satisfied by a straight A path plus a 2x2 loop of A (or B) filling the middle
+        let board = SquareBoardBuilder::with_dims((NonZero::new(4).unwrap(), NonZero::new(4).unwrap()))
+            .add_termini('A', (Location(0, 0), Location(3, 0)))
+            .add_termini('B', (Location(0, 3), Location(3, 3)))
+            .build()
+            .unwrap();
+
+        assert!(matches!(board.check_unique().unwrap(), Uniqueness::Unsolvable));
+        // the paths themselves are fine, but the rest of the board cannot be filled
+        assert!(matches!(board.solve(SolveOptions::default()), Err(SolverFailure::Conflict(features)) if features.iter().all(|feature| matches!(feature, Feature::Filled(_)))));
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn explain_crossing() {
+        let board = crossing().build().unwrap();
+
+        match board.solve(SolveOptions::default()) {
+            Err(SolverFailure::Conflict(features)) => assert_eq!(features, vec![
+                Feature::Termini { display: 'A', locations: (Location(0, 0), Location(1, 1)) },
+                Feature::Termini { display: 'B', locations: (Location(1, 0), Location(0, 1)) },
+            ]),
+            _ => panic!("board should be unsolvable"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn explain_walls_and_holes() {
+        let mut builder = SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(1).unwrap()));
+        builder.add_termini('A', (Location(0, 0), Location(2, 0)));
+
+        let holed = builder.clone()
+            .drop_location(Location(1, 0))
+            .build()
+            .unwrap();
+
+        match holed.solve(SolveOptions::default()) {
+            Err(SolverFailure::Conflict(features)) => assert_eq!(features, vec![
+                Feature::Termini { display: 'A', locations: (Location(0, 0), Location(2, 0)) },
+                Feature::DroppedLocation(Location(1, 0)),
+            ]),
+            _ => panic!("board should be unsolvable"),
+        }
+
+        let walled = builder.clone()
+            .disconnect(UnorderedPair(Location(1, 0), Location(2, 0)))
+            .build()
+            .unwrap();
+
+        match walled.solve(SolveOptions::default()) {
+            Err(SolverFailure::Conflict(features)) => assert_eq!(features, vec![
+                Feature::Termini { display: 'A', locations: (Location(0, 0), Location(2, 0)) },
+                Feature::Wall(UnorderedPair(Location(1, 0), Location(2, 0))),
+            ]),
+            _ => panic!("board should be unsolvable"),
+        }
+
+        assert_eq!(format!("{}", builder.build().unwrap().solve(SolveOptions::default()).unwrap()), "AaA\n");
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn session_toggles() {
+        let board = SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(1).unwrap()))
+            .add_termini('A', (Location(0, 0), Location(2, 0)))
+            .drop_location(Location(1, 0))
+            .disconnect(UnorderedPair(Location(1, 0), Location(2, 0)))
+            .build()
+            .unwrap();
+        let hole = Feature::DroppedLocation(Location(1, 0));
+        let wall = Feature::Wall(UnorderedPair(Location(1, 0), Location(2, 0)));
+
+        let mut session = Session::from(board);
+        assert!(matches!(session.solve(), Err(SolverFailure::Conflict(_))));
+
+        assert!(session.set_enabled(&hole, false));
+        assert_eq!(session.is_enabled(&hole), Some(false));
+        match session.solve() {
+            Err(SolverFailure::Conflict(features)) => assert_eq!(features, vec![
+                Feature::Termini { display: 'A', locations: (Location(0, 0), Location(2, 0)) },
+                wall.clone(),
+            ]),
+            _ => panic!("board should be unsolvable"),
+        }
+
+        session.set_enabled(&wall, false);
+        assert_eq!(format!("{}", session.solve().unwrap()), "AaA\n");
+
+        // switching the termini off leaves nothing to fill the board with
+        session.set_enabled(&Feature::Termini { display: 'A', locations: (Location(0, 0), Location(2, 0)) }, false);
+        assert_eq!(format!("{}", session.board()), "...\n");
+        assert!(session.solve().is_err());
+        session.set_enabled(&Feature::Termini { display: 'A', locations: (Location(0, 0), Location(2, 0)) }, true);
+        assert_eq!(format!("{}", session.solve().unwrap()), "AaA\n");
+
+        assert!(!session.set_enabled(&Feature::Bridge(Location(1, 0)), true));
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn draw_and_hint() {
+        let builder = classic_level_1();
+
+        let board = builder.clone()
+            .draw('A', UnorderedPair(Location(0, 0), Location(0, 1)))
+            .draw('A', UnorderedPair(Location(0, 1), Location(0, 2)))
+            .build()
+            .unwrap();
+
+        assert_eq!(format!("{}", board), "A.B.D
+a.C.E
+a....
+.B.D.
+.ACE.
+");
+        assert_eq!(board.hint().unwrap(), Some(Hint::Segment { display: 'A', locations: UnorderedPair(Location(0, 2), Location(0, 3)) }));
+
+        let solved = solve_every_way(board);
+        assert_eq!(format!("{}", solved), "AbBdD
+abCdE
+abcde
+aBcDe
+aACEe
+");
+        assert_eq!(solved.hint().unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn draw_wrong() {
+        let wrong = Feature::Drawn { display: 'A', locations: UnorderedPair(Location(0, 0), Location(1, 0)) };
+        let board = classic_level_1()
+            .draw('A', UnorderedPair(Location(0, 0), Location(1, 0)))
+            .draw('C', UnorderedPair(Location(2, 1), Location(2, 2)))
+            .build()
+            .unwrap();
+
+        assert!(matches!(board.hint(), Err(SolverFailure::Conflict(features)) if features == vec![wrong.clone()]));
+        assert!(matches!(board.clone().solve(SolveOptions::default()), Err(SolverFailure::Conflict(features)) if features == vec![wrong.clone()]));
+
+        let mut session = Session::from(board);
+        session.set_enabled(&wrong, false);
+        assert_eq!(format!("{}", session.solve().unwrap()), "AbBdD
+abCdE
+abcde
+aBcDe
+aACEe
+");
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn missing_backend_binary() {
+        let board = SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(1).unwrap()))
+            .add_termini('A', (Location(0, 0), Location(2, 0)))
+            .build()
+            .unwrap();
+
+        assert!(matches!(board.solve_with_backend(SolveOptions::default(), || DimacsProcess::new("/nonexistent/sat-solver")), Err(SolverFailure::Backend(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn stub_backend_binary() {
+        let fresh = || SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(1).unwrap()))
+            .add_termini('A', (Location(0, 0), Location(2, 0)))
+            .build()
+            .unwrap();
+        let stub = |script: &str| {
+            let mut process = DimacsProcess::new("sh");
+            process.arg("-c").arg(script);
+            process
+        };
+
+        // an unsolvable answer is explained, which asks the binary again
+        assert!(matches!(fresh().solve_with_backend(SolveOptions::default(), || stub("cat >/dev/null; echo 's UNSATISFIABLE'")), Err(SolverFailure::Inconsistent | SolverFailure::Conflict(_))));
+        // gives up without reading the problem, which must not hang the writer
+        assert!(matches!(fresh().solve_with_backend(SolveOptions::default(), || stub("echo 's MAYBE'")), Err(SolverFailure::Backend(_))));
+
+        // a fixed model, as found by a solver standing in for the binary
+        let dimacs = fresh().to_dimacs();
+        let mut solver = Solver::new();
+        solver.add_formula(&DimacsParser::parse(dimacs.as_bytes()).unwrap());
+        assert!(solver.solve().unwrap());
+        let script = format!("cat >/dev/null; echo 's SATISFIABLE'; echo 'v {} 0'", solver.model().unwrap().iter().map(|lit| lit.to_dimacs()).join(" "));
+
+        assert_eq!(format!("{}", fresh().solve_with_backend(SolveOptions::default(), || stub(&script)).unwrap()), "AaA\n");
+    }
+
+    #[test]
+    #[cfg(feature = "cdcl")]
+    fn cdcl_backend() {
+        let board = level_150().build().unwrap();
+
+        assert_eq!(format!("{}", board.solve_with_backend(SolveOptions::default(), Cdcl::new).unwrap()), "gGfffeeee
+gffHheAae
+gfbBheEAe
+GfbHhCDee
+dfbCccded
+dfbbjJded
+dfFb+Bded
+ddDIjjdEd
+IiiiFJddd
+");
+
+        let crossing = crossing().build().unwrap();
+
+        match crossing.solve_with_backend(SolveOptions::default(), Cdcl::new) {
+            Err(SolverFailure::Conflict(features)) => assert_eq!(features, vec![
+                Feature::Termini { display: 'A', locations: (Location(0, 0), Location(1, 1)) },
+                Feature::Termini { display: 'B', locations: (Location(1, 0), Location(0, 1)) },
+            ]),
+            _ => panic!("board should be unsolvable"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "search")]
+    fn search_unsolvable() {
+        let crossing = crossing().build().unwrap();
+
+        assert!(matches!(crossing.solve_by_search(), Err(SolverFailure::Inconsistent)));
+    }
+
+    #[test]
+    #[cfg(feature = "search")]
+    fn search_most_basic() {
+        let board = classic_level_1().build().unwrap();
+
+        assert_eq!(format!("{}", board.solve_by_search().unwrap()), "AbBdD
+abCdE
+abcde
+aBcDe
+aACEe
+");
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn dimacs_round_trip() {
+        let board = classic_level_1().build().unwrap();
+
+        let dimacs = board.to_dimacs();
+        assert!(dimacs.lines().any(|line| line == "c 1 Node { node: Node { location: Location(0, 0), cell: Terminus { affiliation: 1 } } } affiliation 0"));
+        assert!(dimacs.lines().skip_while(|line| !line.starts_with("p ")).skip(1).all(|line| !line.starts_with('c')));
+
+        // stand in for an external solver
+        let mut solver = Solver::new();
+        solver.add_formula(&DimacsParser::parse(dimacs.as_bytes()).unwrap());
+        assert!(solver.solve().unwrap());
+        let model = format!("s SATISFIABLE\nv {} 0\n", solver.model().unwrap().iter().map(|lit| lit.to_dimacs()).join(" "));
+
+        assert_eq!(format!("{}", board.solve_from_dimacs_model(model.as_bytes()).unwrap()), "AbBdD
+abCdE
+abcde
+aBcDe
+aACEe
+");
+    }
+
+    #[test]
+    #[cfg(feature = "sat")]
+    fn dimacs_bad_models() {
+        let fresh = || SquareBoardBuilder::with_dims((NonZero::new(3).unwrap(), NonZero::new(1).unwrap()))
+            .add_termini('A', (Location(0, 0), Location(2, 0)))
+            .build()
+            .unwrap();
+
+        assert!(matches!(fresh().solve_from_dimacs_model("s UNSATISFIABLE\n".as_bytes()), Err(SolverFailure::Inconsistent)));
+        assert!(matches!(fresh().solve_from_dimacs_model("s SATISFIABLE\nv 1 2 3 0\n".as_bytes()), Err(SolverFailure::Backend(_))));
+        assert!(matches!(fresh().solve_from_dimacs_model("v x 0\n".as_bytes()), Err(SolverFailure::Backend(_))));
+    }
+
+    #[test]
+    fn propagate_most_basic() {
+        let board = classic_level_1().build().unwrap();
+
+        let deductions = board.propagate();
+        // the bottom left corner has only two neighbours
+        assert_eq!(deductions[0], Deduction { rule: Rule::TwoOpenNeighbours, fact: Fact::Connected(UnorderedPair(Location(0, 4), Location(0, 3))) });
+
+        // simple rules alone are enough to fill this board
+        let filled = deductions.iter()
+            .filter_map(|deduction| match deduction.fact {
+                Fact::Affiliated(Location(x, y), display) => Some(((x, y), display)),
+                _ => None,
+            })
+            .sorted()
+            .collect_vec();
+        assert_eq!(filled, vec![
+            ((0, 1), 'A'), ((0, 2), 'A'), ((0, 3), 'A'), ((0, 4), 'A'),
+            ((1, 0), 'B'), ((1, 1), 'B'), ((1, 2), 'B'),
+            ((2, 2), 'C'), ((2, 3), 'C'),
+            ((3, 0), 'D'), ((3, 1), 'D'), ((3, 2), 'D'),
+            ((4, 2), 'E'), ((4, 3), 'E'), ((4, 4), 'E'),
+        ]);
+    }
+}
@@ -0,0 +1,41 @@
+//! Step-by-step explanations of how a person could solve a board, as in [`Board::trace`](crate::Board::trace).
+//!
+//! A trace is solved by the same [`Technique`](crate::rating::Technique)s as a [`Rating`](crate::rating::Rating), each fact it finds becoming a [`Step`] which says why it holds.
+//! Only when no technique makes progress is a connection guessed, taken from some solution of the board.
+//! With the `serde` feature, traces can be serialized, e.g. to drive a tutorial.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::propagation::{Fact, Rule};
+
+/// Why the facts of a [`Step`] hold.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Reason {
+    /// A local [`Rule`] forced it, given the board and the steps before; see [`Technique::ForcedMove`](crate::rating::Technique::ForcedMove).
+    Rule(Rule),
+    /// Only one path can reach a region of empty cells, so it fills them; see [`Technique::RegionParity`](crate::rating::Technique::RegionParity).
+    Region,
+    /// Every route between the two loose ends of a path passes through these cells; see [`Technique::Bottleneck`](crate::rating::Technique::Bottleneck).
+    Bottleneck,
+    /// Supposing the opposite led to a contradiction within this many steps of lookahead; see [`Technique::Lookahead`](crate::rating::Technique::Lookahead).
+    Contradiction {
+        /// How deep the lookahead went, at least 1.
+        depth: usize,
+    },
+    /// No technique made progress, so this was taken from a solution; a person would have to guess it, or try it and see.
+    Guess,
+}
+
+/// One step of solving a board, as listed by [`Board::trace`](crate::Board::trace).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Step {
+    /// Why the facts hold.
+    pub reason: Reason,
+    /// What this step found; more than one fact only for [`Reason::Region`] and [`Reason::Bottleneck`].
+    pub facts: Vec<Fact>,
+    /// The board once this step is taken, as printed by its [`Display`](std::fmt::Display) implementation, with every cell known so far filled in.
+    pub board: String,
+}
@@ -1,3 +1,5 @@
+//! Bindings for use of this crate from JavaScript.
+
 use wasm_bindgen::prelude::wasm_bindgen;
 
 #[wasm_bindgen]
@@ -5,6 +7,7 @@ extern {
     fn alert(s: &str);
 }
 
+/// Placeholder export for checking the WASM build end to end.
 #[wasm_bindgen]
 pub fn bingus() {
     alert("bingus");